@@ -25,6 +25,8 @@ pub fn run(args: &mut Args) -> Result<()> {
     Ok(())
 }
 
+const DIGIT_WORDS: [&str; 9] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+
 struct AmendedCalibrationValue(String);
 
 impl AmendedCalibrationValue {
@@ -35,9 +37,9 @@ impl AmendedCalibrationValue {
     }
 
     fn calculate_calibration_value_part2(&self) -> u32 {
-        let digits = self.extract_digits_special();
-        let calibration_value_string = self.recover_calibration_value_string(digits);
-        calibration_value_string.parse::<u32>().unwrap()
+        let first = self.first_digit_special().expect("Unrecoverable Error: No digits found in Amended Calibration Value");
+        let last = self.last_digit_special().unwrap();
+        first * 10 + last
     }
 
     fn recover_calibration_value_string(&self, digits: String) -> String {
@@ -71,50 +73,38 @@ impl AmendedCalibrationValue {
         found_digits
     }
 
-    fn extract_digits_special(&self) -> String {
-        let mut found_digits = String::new();
-        let mut possible_word = String::new();
-        for c in self.0.chars() {
-            if let '0'..='9' = c {
-                found_digits.push(c);
-                possible_word.clear();
-            } else {
-                possible_word.push(c);
-                let real_word = self.contains_digit_word(&possible_word);
-                if !real_word.is_empty() {
-                    found_digits.push_str(&real_word);
-                    possible_word.clear();
-                    possible_word.push(c);
-                }
-            }
-        }
-        found_digits
+    fn first_digit_special(&self) -> Option<u32> {
+        (0..self.0.len()).find_map(|i| self.digit_at(i))
     }
 
-    fn word_to_digit(&self, word: &str) -> String {
-        let digit: String = match word {
-            "one" => String::from("1"),
-            "two" => String::from("2"),
-            "three" => String::from("3"),
-            "four" => String::from("4"),
-            "five" => String::from("5"),
-            "six" => String::from("6"),
-            "seven" => String::from("7"),
-            "eight" => String::from("8"),
-            "nine" => String::from("9"),
-            _ => String::from("")
-        };
-        digit
+    fn last_digit_special(&self) -> Option<u32> {
+        (0..self.0.len()).rev().find_map(|i| self.digit_at(i))
     }
-    
-    fn contains_digit_word(&self, word: &str) -> String {
-        let digits: [&str; 9] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
-        for digit in digits {
-            if word.contains(digit) {
-                return self.word_to_digit(digit);
-            }
+
+    /// Digit starting at byte `i`, written either as a numeral or as a word.
+    /// Each position is matched on its own, so words sharing letters like
+    /// "eightwo" are both found without any buffering. This is still a check
+    /// per byte rather than one pass over the line, but the byte picks the
+    /// candidate words, so at most two are compared.
+    fn digit_at(&self, i: usize) -> Option<u32> {
+        let rest = self.0.get(i..)?;
+        let first = rest.chars().next()?;
+        if let Some(digit) = first.to_digit(10) {
+            return Some(digit);
         }
-        String::from("")
+        let candidates: &[usize] = match first {
+            'o' => &[0],
+            't' => &[1, 2],
+            'f' => &[3, 4],
+            's' => &[5, 6],
+            'e' => &[7],
+            'n' => &[8],
+            _ => &[],
+        };
+        candidates
+            .iter()
+            .find(|&&w| rest.starts_with(DIGIT_WORDS[w]))
+            .map(|&w| w as u32 + 1)
     }
 }
 
@@ -158,17 +148,13 @@ mod tests {
 
     #[test]
     fn can_convert_word_to_digit() {
-        let acv = AmendedCalibrationValue(String::from(""));
-        assert_eq!(acv.word_to_digit("one"), String::from("1"));
-        assert_eq!(acv.word_to_digit("two"), String::from("2"));
-        assert_eq!(acv.word_to_digit("three"), String::from("3"));
-        assert_eq!(acv.word_to_digit("four"), String::from("4"));
-        assert_eq!(acv.word_to_digit("five"), String::from("5"));
-        assert_eq!(acv.word_to_digit("six"), String::from("6"));
-        assert_eq!(acv.word_to_digit("seven"), String::from("7"));
-        assert_eq!(acv.word_to_digit("eight"), String::from("8"));
-        assert_eq!(acv.word_to_digit("nine"), String::from("9"));
-        assert_eq!(acv.word_to_digit("asdfasdfh"), String::from(""));
+        for (i, word) in DIGIT_WORDS.iter().enumerate() {
+            let acv = AmendedCalibrationValue(format!("x{}", word));
+            assert_eq!(acv.digit_at(1), Some(i as u32 + 1), "{}", word);
+            assert_eq!(acv.digit_at(0), None);
+        }
+        let acv = AmendedCalibrationValue(String::from("asdfasdfh"));
+        assert_eq!((0..9).find_map(|i| acv.digit_at(i)), None);
     }
 
     #[test]
@@ -180,12 +166,24 @@ mod tests {
         let case5 = AmendedCalibrationValue(String::from("4nineeightseven2"));
         let case6 = AmendedCalibrationValue(String::from("zoneight234"));
         let case7 = AmendedCalibrationValue(String::from("7pqrstsixteen"));
-        assert_eq!(case1.extract_digits_special(), "219");
-        assert_eq!(case2.extract_digits_special(), "823");
-        assert_eq!(case3.extract_digits_special(), "123");
-        assert_eq!(case4.extract_digits_special(), "2134");
-        assert_eq!(case5.extract_digits_special(), "49872");
-        assert_eq!(case6.extract_digits_special(), "18234");
-        assert_eq!(case7.extract_digits_special(), "76");
+        assert_eq!(case1.calculate_calibration_value_part2(), 29);
+        assert_eq!(case2.calculate_calibration_value_part2(), 83);
+        assert_eq!(case3.calculate_calibration_value_part2(), 13);
+        assert_eq!(case4.calculate_calibration_value_part2(), 24);
+        assert_eq!(case5.calculate_calibration_value_part2(), 42);
+        assert_eq!(case6.calculate_calibration_value_part2(), 14);
+        assert_eq!(case7.calculate_calibration_value_part2(), 76);
+    }
+
+    #[test]
+    fn can_find_overlapping_digit_words_from_both_ends() {
+        let case1 = AmendedCalibrationValue(String::from("eightwo"));
+        let case2 = AmendedCalibrationValue(String::from("xoneight"));
+        let case3 = AmendedCalibrationValue(String::from("twone"));
+        let case4 = AmendedCalibrationValue(String::from("abc"));
+        assert_eq!((case1.first_digit_special(), case1.last_digit_special()), (Some(8), Some(2)));
+        assert_eq!((case2.first_digit_special(), case2.last_digit_special()), (Some(1), Some(8)));
+        assert_eq!((case3.first_digit_special(), case3.last_digit_special()), (Some(2), Some(1)));
+        assert_eq!((case4.first_digit_special(), case4.last_digit_special()), (None, None));
     }
 }
\ No newline at end of file
@@ -1,12 +1,14 @@
-use std::{io::BufRead, collections::HashMap};
+use std::{io::BufRead, collections::HashSet};
 
 use crate::helpers::{self, Args};
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use itertools::Itertools;
 use log::info;
 
 const EXPANSION_COEFFICIENT_PART_1: i64 = 2;
 const EXPANSION_COEFFICIENT_PART_2: i64 = 1000000;
+const MAX_RENDERED_CELLS: i128 = 10_000_000;
+const HISTOGRAM_BUCKETS: i128 = 10;
 
 pub fn run(args: &mut Args) -> Result<()> {
     info!(target: "Day 11", "Running...");
@@ -17,58 +19,361 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 11", "Solving...");
 
-    let mut total_part_1 = 0;
-    let mut total_part_2 = 0;
-
     let mut galaxy_location: Vec<(usize, usize)> = Vec::new();
-    let mut horizontal_null_space: HashMap<usize, bool> = HashMap::new();
-    let mut vertical_null_space: HashMap<usize, bool> = HashMap::new();
-   
+    let mut width = 0;
+    let mut height = 0;
+
     for (y, line) in reader.lines().enumerate() {
-        for (x, c) in line.unwrap().chars().enumerate() {
+        let l = line.with_context(|| "Couldn't read line")?;
+        for (x, c) in l.chars().enumerate() {
             if c != '.' {
                 galaxy_location.push((x, y));
-                vertical_null_space.entry(x).and_modify(|entry| *entry = false).or_insert(false);
-                horizontal_null_space.entry(y).and_modify(|entry| *entry = false).or_insert(false);
             }
-            vertical_null_space.entry(x).or_insert(true);
         }
-        horizontal_null_space.entry(y).or_insert(true);
+        width = width.max(l.chars().count());
+        height = y + 1;
     }
-    
-    for combo in galaxy_location.iter().combinations(2) {
-        total_part_1 += distance(combo[0], combo[1], &vertical_null_space, &horizontal_null_space, EXPANSION_COEFFICIENT_PART_1);
-        total_part_2 += distance(combo[0], combo[1], &vertical_null_space, &horizontal_null_space, EXPANSION_COEFFICIENT_PART_2);
+
+    let universe = Universe::new(galaxy_location, width, height);
+
+    if args.render {
+        let factors = if args.expansion.is_empty() { vec![EXPANSION_COEFFICIENT_PART_1] } else { args.expansion.clone() };
+        for ec in factors {
+            println!("Expansion {}:", ec);
+            for row in universe.render(ec)? {
+                println!("{}", row);
+            }
+        }
+        return Ok(());
     }
 
-    println!("Part 1: {}", total_part_1);
-    println!("Part 2: {}", total_part_2);
+    if !args.pair.is_empty() || args.stats {
+        let factors = if args.expansion.is_empty() { vec![EXPANSION_COEFFICIENT_PART_1] } else { args.expansion.clone() };
+        for ec in factors {
+            println!("Expansion {}:", ec);
+            for pair in args.pair.chunks(2) {
+                let distance = universe.distance(pair[0], pair[1], ec)?;
+                println!("  Galaxy #{} to #{}: {}", pair[0], pair[1], distance);
+            }
+            if args.stats {
+                if let Some((a, b, d)) = universe.nearest_pair(ec) {
+                    println!("  Nearest pair: #{} and #{}, distance {}", a, b, d);
+                }
+                if let Some((a, b, d)) = universe.farthest_pair(ec) {
+                    println!("  Farthest pair: #{} and #{}, distance {}", a, b, d);
+                }
+                println!("  Distance histogram:");
+                for (lo, hi, count) in universe.histogram(ec, HISTOGRAM_BUCKETS) {
+                    println!("    [{}, {}): {}", lo, hi, count);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if args.expansion.is_empty() {
+        println!("Part 1: {}", universe.sum_of_distances(EXPANSION_COEFFICIENT_PART_1));
+        println!("Part 2: {}", universe.sum_of_distances(EXPANSION_COEFFICIENT_PART_2));
+    }
+    for &ec in &args.expansion {
+        println!("Expansion {}: {}", ec, universe.sum_of_distances(ec));
+    }
 
     Ok(())
 }
 
-fn distance(start: &(usize, usize), end: &(usize, usize), vertical_map: &HashMap<usize, bool>, horizontal_map: &HashMap<usize, bool>, ec: i64) -> i64 {
-    let x = cross_null_space(start.0, end.0, vertical_map);
-    let y = cross_null_space(start.1, end.1, horizontal_map);
-    (start.0 as i64 - end.0 as i64).abs() + (if x > 0 {x * (ec - 1)} else {0}) + (start.1 as i64 - end.1 as i64).abs() + (if y > 0 {y * (ec - 1)} else {0})
+/// Galaxies in reading order plus, for each axis, how many empty columns
+/// (or rows) come before every index. With those prefix counts a galaxy's
+/// expanded position is a lookup, so nothing scans the space between pairs.
+#[derive(Debug, Clone)]
+struct Universe {
+    galaxies: Vec<(usize, usize)>,
+    width: usize,
+    height: usize,
+    empty_columns_before: Vec<usize>,
+    empty_rows_before: Vec<usize>,
 }
 
-fn cross_null_space(start: usize, end: usize, space_map: &HashMap<usize, bool>) -> i64 {
-    let mut crossing_count = 0;
-    let new_start: usize;
-    let new_end: usize;
-
-    if start > end {
-        new_start = end;
-        new_end = start;
-    } else {
-        new_start = start;
-        new_end = end;
-    }
-    for i in new_start..new_end {
-        if *space_map.get(&i).unwrap() {
-            crossing_count += 1;
+impl Universe {
+    fn new(galaxies: Vec<(usize, usize)>, width: usize, height: usize) -> Universe {
+        let mut column_has_galaxy = vec![false; width];
+        let mut row_has_galaxy = vec![false; height];
+        for &(x, y) in &galaxies {
+            column_has_galaxy[x] = true;
+            row_has_galaxy[y] = true;
         }
+
+        Universe {
+            galaxies,
+            width,
+            height,
+            empty_columns_before: empty_before(&column_has_galaxy),
+            empty_rows_before: empty_before(&row_has_galaxy),
+        }
+    }
+
+    /// Position of a galaxy once every empty row and column is replaced by
+    /// `ec` copies of itself.
+    fn expanded(&self, galaxy: (usize, usize), ec: i64) -> (i128, i128) {
+        let grow = ec as i128 - 1;
+        (
+            galaxy.0 as i128 + grow * self.empty_columns_before[galaxy.0] as i128,
+            galaxy.1 as i128 + grow * self.empty_rows_before[galaxy.1] as i128,
+        )
     }
-    crossing_count
-}
\ No newline at end of file
+
+    /// Manhattan distances split by axis, so the sum over all pairs is the
+    /// sum of pairwise gaps along x plus the same along y.
+    fn sum_of_distances(&self, ec: i64) -> i128 {
+        let (xs, ys): (Vec<i128>, Vec<i128>) = self.galaxies.iter().map(|&g| self.expanded(g, ec)).unzip();
+        sum_of_gaps(xs) + sum_of_gaps(ys)
+    }
+
+    /// Distance between two galaxies, numbered from 1 in reading order.
+    fn distance(&self, a: usize, b: usize, ec: i64) -> Result<i128> {
+        let lookup = |id: usize| {
+            id.checked_sub(1)
+                .and_then(|i| self.galaxies.get(i))
+                .map(|&g| self.expanded(g, ec))
+                .with_context(|| format!("No galaxy #{}, there are {}", id, self.galaxies.len()))
+        };
+        let (start, end) = (lookup(a)?, lookup(b)?);
+        Ok((start.0 - end.0).abs() + (start.1 - end.1).abs())
+    }
+
+    /// Closest pair as `(#a, #b, distance)`. Sweeps galaxies by x and stops
+    /// each scan once the x gap alone is no better than the best so far.
+    fn nearest_pair(&self, ec: i64) -> Option<(usize, usize, i128)> {
+        let points: Vec<(i128, i128, usize)> = self
+            .galaxies
+            .iter()
+            .enumerate()
+            .map(|(i, &g)| {
+                let (x, y) = self.expanded(g, ec);
+                (x, y, i + 1)
+            })
+            .sorted_unstable()
+            .collect();
+
+        let mut best: Option<(usize, usize, i128)> = None;
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                if best.is_some_and(|(_, _, d)| b.0 - a.0 >= d) {
+                    break;
+                }
+                let d = (b.0 - a.0) + (b.1 - a.1).abs();
+                if best.is_none_or(|(_, _, best_d)| d < best_d) {
+                    best = Some((a.2.min(b.2), a.2.max(b.2), d));
+                }
+            }
+        }
+        best
+    }
+
+    /// Farthest pair as `(#a, #b, distance)`. A Manhattan distance is the
+    /// larger of the spreads of `x + y` and `x - y`, so only the extremes
+    /// of those two sums matter.
+    fn farthest_pair(&self, ec: i64) -> Option<(usize, usize, i128)> {
+        let projections: [fn((i128, i128)) -> i128; 2] = [|(x, y)| x + y, |(x, y)| x - y];
+        projections
+            .iter()
+            .filter_map(|projection| {
+                let values = self.galaxies.iter().enumerate().map(|(i, &g)| (projection(self.expanded(g, ec)), i + 1));
+                let ((lo, a), (hi, b)) = values.minmax().into_option()?;
+                Some((a.min(b), a.max(b), hi - lo))
+            })
+            .max_by_key(|&(_, _, d)| d)
+            .filter(|&(a, b, _)| a != b)
+    }
+
+    /// Counts of pairwise distances in `buckets` equal half-open ranges
+    /// spanning the nearest to the farthest pair. Visits every pair.
+    fn histogram(&self, ec: i64, buckets: i128) -> Vec<(i128, i128, usize)> {
+        let (Some((_, _, min)), Some((_, _, max))) = (self.nearest_pair(ec), self.farthest_pair(ec)) else {
+            return Vec::new();
+        };
+        let width = ((max - min + 1) + buckets - 1) / buckets;
+        let mut counts = vec![0; buckets as usize];
+        let points: Vec<(i128, i128)> = self.galaxies.iter().map(|&g| self.expanded(g, ec)).collect();
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                let d = (a.0 - b.0).abs() + (a.1 - b.1).abs();
+                counts[((d - min) / width) as usize] += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| (min + i as i128 * width, min + (i as i128 + 1) * width, count))
+            .take_while(|&(lo, _, _)| lo <= max)
+            .collect()
+    }
+
+    /// Rows of the universe after expansion, drawn with `#` for galaxies.
+    /// Refuses factors that would produce an unreasonably large map.
+    fn render(&self, ec: i64) -> Result<Vec<String>> {
+        let grow = ec as i128 - 1;
+        let width = self.width as i128 + grow * self.empty_columns_before[self.width] as i128;
+        let height = self.height as i128 + grow * self.empty_rows_before[self.height] as i128;
+        ensure!(
+            width.checked_mul(height).is_some_and(|cells| cells <= MAX_RENDERED_CELLS),
+            "Expanded universe would be {}x{}, too large to render with factor {}",
+            width,
+            height,
+            ec
+        );
+
+        let galaxies: HashSet<(usize, usize)> = self.galaxies.iter().copied().collect();
+        let mut rows: Vec<String> = Vec::new();
+        for y in 0..self.height {
+            let mut row = String::new();
+            for x in 0..self.width {
+                let empty_column = self.empty_columns_before[x + 1] > self.empty_columns_before[x];
+                let c = if galaxies.contains(&(x, y)) { '#' } else { '.' };
+                row.extend(std::iter::repeat_n(c, if empty_column { ec as usize } else { 1 }));
+            }
+            let empty_row = self.empty_rows_before[y + 1] > self.empty_rows_before[y];
+            let copies = if empty_row { ec as usize } else { 1 };
+            rows.extend(std::iter::repeat_n(row, copies));
+        }
+        Ok(rows)
+    }
+}
+
+/// Prefix counts of empty slots: entry `i` is how many of `occupied[..i]`
+/// are false.
+fn empty_before(occupied: &[bool]) -> Vec<usize> {
+    let mut counts = Vec::with_capacity(occupied.len() + 1);
+    let mut empty = 0;
+    counts.push(empty);
+    for &filled in occupied {
+        if !filled {
+            empty += 1;
+        }
+        counts.push(empty);
+    }
+    counts
+}
+
+/// Sum of `|a - b|` over all pairs. Once sorted, the `i`th value is larger
+/// than the `i` before it and smaller than the `n - 1 - i` after it.
+fn sum_of_gaps(mut values: Vec<i128>) -> i128 {
+    values.sort_unstable();
+    let n = values.len() as i128;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| v * (2 * i as i128 - n + 1))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 10] = [
+        "...#......",
+        ".......#..",
+        "#.........",
+        "..........",
+        "......#...",
+        ".#........",
+        ".........#",
+        "..........",
+        ".......#..",
+        "#...#.....",
+    ];
+
+    fn universe(rows: &[&str]) -> Universe {
+        let galaxies = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.chars().enumerate().filter(|&(_, c)| c != '.').map(move |(x, _)| (x, y)))
+            .collect();
+        Universe::new(galaxies, rows[0].len(), rows.len())
+    }
+
+    #[test]
+    fn can_sum_example_distances() {
+        let universe = universe(&EXAMPLE);
+        assert_eq!(universe.sum_of_distances(2), 374);
+        assert_eq!(universe.sum_of_distances(10), 1030);
+        assert_eq!(universe.sum_of_distances(100), 8410);
+    }
+
+    #[test]
+    fn can_render_example_expansion() {
+        let expected = [
+            "....#........",
+            ".........#...",
+            "#............",
+            ".............",
+            ".............",
+            "........#....",
+            ".#...........",
+            "............#",
+            ".............",
+            ".............",
+            ".........#...",
+            "#....#.......",
+        ];
+        assert_eq!(universe(&EXAMPLE).render(2).unwrap(), expected);
+        assert!(universe(&EXAMPLE).render(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn can_count_empty_slots_before_each_index() {
+        assert_eq!(empty_before(&[true, false, false, true, false]), vec![0, 0, 1, 2, 2, 3]);
+        assert_eq!(empty_before(&[]), vec![0]);
+    }
+
+    #[test]
+    fn sum_of_gaps_matches_pairwise_sum() {
+        let universe = universe(&[".#..#", ".....", "#...#", "..#.."]);
+        for ec in [1, 2, 7] {
+            let points: Vec<(i128, i128)> = universe.galaxies.iter().map(|&g| universe.expanded(g, ec)).collect();
+            let naive: i128 = points
+                .iter()
+                .tuple_combinations()
+                .map(|(a, b)| (a.0 - b.0).abs() + (a.1 - b.1).abs())
+                .sum();
+            assert_eq!(universe.sum_of_distances(ec), naive);
+            let xs: Vec<i128> = points.iter().map(|p| p.0).collect();
+            assert_eq!(sum_of_gaps(xs.clone()), xs.iter().tuple_combinations().map(|(a, b)| (a - b).abs()).sum::<i128>());
+        }
+    }
+
+    #[test]
+    fn nearest_and_farthest_pairs_match_brute_force() {
+        let universe = universe(&EXAMPLE);
+        for ec in [1, 2, 10, 1000000] {
+            let distances: Vec<i128> = (1..=universe.galaxies.len())
+                .tuple_combinations()
+                .map(|(a, b)| universe.distance(a, b, ec).unwrap())
+                .collect();
+            let (a, b, nearest) = universe.nearest_pair(ec).unwrap();
+            assert_eq!(nearest, *distances.iter().min().unwrap());
+            assert_eq!(universe.distance(a, b, ec).unwrap(), nearest);
+            let (a, b, farthest) = universe.farthest_pair(ec).unwrap();
+            assert_eq!(farthest, *distances.iter().max().unwrap());
+            assert_eq!(universe.distance(a, b, ec).unwrap(), farthest);
+        }
+        assert_eq!(Universe::new(vec![(0, 0)], 1, 1).nearest_pair(2), None);
+        assert_eq!(Universe::new(vec![(0, 0)], 1, 1).farthest_pair(2), None);
+    }
+
+    #[test]
+    fn histogram_counts_every_pair_once() {
+        let universe = universe(&EXAMPLE);
+        let n = universe.galaxies.len();
+        for ec in [2, 10, 100] {
+            let histogram = universe.histogram(ec, HISTOGRAM_BUCKETS);
+            assert_eq!(histogram.iter().map(|&(_, _, count)| count).sum::<usize>(), n * (n - 1) / 2);
+            assert!(histogram.windows(2).all(|w| w[0].1 == w[1].0));
+            let distances = (1..=n).tuple_combinations().map(|(a, b)| universe.distance(a, b, ec).unwrap());
+            for (lo, hi, count) in histogram {
+                assert_eq!(distances.clone().filter(|d| (lo..hi).contains(d)).count(), count);
+            }
+        }
+    }
+}
@@ -1,9 +1,17 @@
 use std::io::BufRead;
 
 use crate::helpers::{self, Args};
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
+use itertools::Itertools;
 use log::info;
 
+const DEFAULT_UNFOLD: usize = 5;
+const MAX_UNFOLDED_LENGTH: usize = 100_000;
+const MAX_BRUTE_FORCE_UNKNOWNS: usize = 16;
+const VERIFY_RANDOM_ROWS: usize = 5000;
+const VERIFY_SEED: u64 = 0x2023_1212;
+const NONOGRAM_SOLUTION_CAP: usize = 1000;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Spring {
     Operational,
@@ -20,70 +28,430 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 12", "Solving...");
 
-    let mut total_part_1 = 0;
-    let mut total_part_2 = 0;
-    let mut condition: Vec<Spring> = Vec::new();
-    let mut condition_part_2: Vec<Spring> = Vec::new();
-    let mut group_sizes: Vec<usize> = Vec::new();
-    let mut group_sizes_part_2: Vec<usize> = Vec::new();
+    if args.nonogram {
+        let lines = reader.lines().collect::<Result<Vec<String>, _>>().with_context(|| "Couldn't read line")?;
+        let nonogram = Nonogram::parse(&lines).with_context(|| "Invalid nonogram clues")?;
+        let (count, first) = nonogram.solve();
+        match first {
+            Some(grid) => {
+                for row in grid {
+                    println!("{}", row.into_iter().map(spring_char).collect::<String>());
+                }
+                if count >= NONOGRAM_SOLUTION_CAP {
+                    println!("At least {} solutions", count);
+                } else {
+                    println!("{} solution{}", count, if count == 1 { "" } else { "s" });
+                }
+            }
+            None => println!("No solution"),
+        }
+        return Ok(());
+    }
 
+    let unfold = args.unfold.map_or(DEFAULT_UNFOLD, |n| n as usize);
+    let mut total_part_1: u128 = 0;
+    let mut total_part_2: u128 = 0;
+    let mut verified = 0;
 
-    for line in reader.lines() {
+    for (idx, line) in reader.lines().enumerate() {
         let l = line.unwrap();
         let inputs = l.split_whitespace().collect::<Vec<&str>>();
-        condition = inputs[0].chars().map(|x| parse_springs(x)).collect::<Vec<Spring>>();
-        group_sizes = inputs[1].trim().split(',').map(|x| x.parse::<usize>().unwrap()).collect();
-        condition_part_2 = condition.iter().copied().chain([Spring::Unknown]).cycle().take(condition.len() * 5 + 4).collect();
-        group_sizes_part_2 = group_sizes.iter().copied().cycle().take(group_sizes.len() * 5).collect();
-        total_part_1 += find_arrangements(condition, group_sizes);
-        total_part_2 += find_arrangements(condition_part_2, group_sizes_part_2);
+        let condition = inputs[0].chars().map(parse_springs).collect::<Vec<Spring>>();
+        let group_sizes: Vec<usize> = inputs[1].trim().split(',').map(|x| x.parse::<usize>().unwrap()).collect();
+        if let Some(cap) = args.list {
+            let (count, listing) = list_arrangements(condition.clone(), &group_sizes, cap);
+            let count = count.with_context(|| format!("Arrangement count overflows u128 on line {}", idx + 1))?;
+            println!("{}: {} arrangements", l, count);
+            for arrangement in &listing {
+                println!("  {}", arrangement);
+            }
+            if count > listing.len() as u128 {
+                println!("  ... and {} more", count - listing.len() as u128);
+            }
+        }
+        if args.verify && condition.iter().filter(|&&s| s == Spring::Unknown).count() <= MAX_BRUTE_FORCE_UNKNOWNS {
+            check_against_brute_force(&condition, &group_sizes).with_context(|| format!("Line {}", idx + 1))?;
+            verified += 1;
+        }
+        let unfolded_length = condition
+            .len()
+            .checked_mul(unfold)
+            .and_then(|n| n.checked_add(unfold - 1))
+            .filter(|&n| n <= MAX_UNFOLDED_LENGTH && group_sizes.len() <= n)
+            .with_context(|| format!("Line {} unfolded {} times is longer than {} springs", idx + 1, unfold, MAX_UNFOLDED_LENGTH))?;
+        let condition_part_2 = condition.iter().copied().chain([Spring::Unknown]).cycle().take(unfolded_length).collect();
+        let group_sizes_part_2 = group_sizes.iter().copied().cycle().take(group_sizes.len() * unfold).collect();
+        let overflow = || format!("Arrangement count overflows u128 on line {}", idx + 1);
+        total_part_1 = find_arrangements(condition, group_sizes)
+            .and_then(|count| total_part_1.checked_add(count))
+            .with_context(overflow)?;
+        total_part_2 = find_arrangements(condition_part_2, group_sizes_part_2)
+            .and_then(|count| total_part_2.checked_add(count))
+            .with_context(overflow)?;
     }  
 
+    if args.verify {
+        let mut rng = XorShift(VERIFY_SEED);
+        for _ in 0..VERIFY_RANDOM_ROWS {
+            let (condition, group_sizes) = random_row(&mut rng);
+            check_against_brute_force(&condition, &group_sizes)?;
+        }
+        println!("Verified {} input rows and {} random rows against brute force", verified, VERIFY_RANDOM_ROWS);
+    }
+
     println!("Part 1: {}", total_part_1);
     println!("Part 2: {}", total_part_2);
 
     Ok(())
 }
 
-fn find_arrangements(mut condition: Vec<Spring>, groupings: Vec<usize>) -> u64 {
+/// Number of arrangements, or `None` if it doesn't fit in a `u128`. Only
+/// keeps one layer of the table at a time, so memory grows with the row
+/// length alone however far the row is unfolded.
+fn find_arrangements(mut condition: Vec<Spring>, groupings: Vec<usize>) -> Option<u128> {
     condition.push(Spring::Operational);
-    let mut cache = vec![vec![None::<u64>; condition.len()]; groupings.len()];
-    recurse_arrangements(&condition, &groupings, &mut cache)
+    let operational_before = operational_before(&condition);
+    let layer = groupings.iter().rev().fold(last_layer(&condition), |later, &group_size| {
+        next_layer(&condition, &operational_before, group_size, &later)
+    });
+    layer[0]
+}
+
+/// Every layer of the arrangement table: entry `[g][p]` counts the ways to
+/// place `groupings[g..]` in `condition[p..]`, `None` once that overflows.
+/// Filled from the last group back with no recursion, so long rows can't
+/// run out of stack.
+fn arrangement_table(condition: &[Spring], groupings: &[usize]) -> Vec<Vec<Option<u128>>> {
+    let operational_before = operational_before(condition);
+    let mut table = vec![last_layer(condition)];
+    for &group_size in groupings.iter().rev() {
+        let layer = next_layer(condition, &operational_before, group_size, table.last().unwrap());
+        table.push(layer);
+    }
+    table.reverse();
+    table
+}
+
+/// Ways to place no groups at all: one if nothing from `p` on is damaged.
+fn last_layer(condition: &[Spring]) -> Vec<Option<u128>> {
+    let mut layer = vec![Some(1); condition.len() + 1];
+    for p in (0..condition.len()).rev() {
+        layer[p] = if condition[p] == Spring::Damaged { Some(0) } else { layer[p + 1] };
+    }
+    layer
+}
+
+/// Ways to place a group of `group_size` followed by the groups counted in
+/// `later`: either the spring at `p` is operational and the group starts
+/// further on, or the group starts at `p` and an operational spring ends it.
+fn next_layer(condition: &[Spring], operational_before: &[usize], group_size: usize, later: &[Option<u128>]) -> Vec<Option<u128>> {
+    let mut layer = vec![Some(0); condition.len() + 1];
+    for p in (0..condition.len()).rev() {
+        let mut count = Some(0u128);
+        if condition[p] != Spring::Damaged {
+            count = count.zip(layer[p + 1]).and_then(|(a, b)| a.checked_add(b));
+        }
+        if group_fits(condition, operational_before, p, group_size) {
+            count = count.zip(later[p + group_size + 1]).and_then(|(a, b)| a.checked_add(b));
+        }
+        layer[p] = count;
+    }
+    layer
+}
+
+/// Whether a damaged group of `group_size` can start at `p`: none of its
+/// springs are operational and the spring after it isn't damaged.
+fn group_fits(condition: &[Spring], operational_before: &[usize], p: usize, group_size: usize) -> bool {
+    p + group_size < condition.len()
+        && operational_before[p + group_size] == operational_before[p]
+        && condition[p + group_size] != Spring::Damaged
+}
+
+/// Prefix counts: entry `i` is how many of `condition[..i]` are operational.
+fn operational_before(condition: &[Spring]) -> Vec<usize> {
+    let mut counts = vec![0; condition.len() + 1];
+    for (i, &s) in condition.iter().enumerate() {
+        counts[i + 1] = counts[i] + usize::from(s == Spring::Operational);
+    }
+    counts
 }
 
-fn recurse_arrangements(condition: &[Spring], groupings: &[usize], cache: &mut [Vec<Option<u64>>]) -> u64 {
-    if groupings.is_empty() {
-        if condition.contains(&Spring::Damaged) {
-            return 0;
-        } else {
-            return 1;
+/// Count plus up to `cap` concrete arrangements as `#`/`.` strings. Walks the
+/// same branches the table counts and skips those counted as zero, so the
+/// listing is exactly the first `cap` of what gets counted.
+fn list_arrangements(mut condition: Vec<Spring>, groupings: &[usize], cap: usize) -> (Option<u128>, Vec<String>) {
+    condition.push(Spring::Operational);
+    let operational_before = operational_before(&condition);
+    let table = arrangement_table(&condition, groupings);
+    let mut listing: Vec<String> = Vec::new();
+
+    // Depth first with the operational branch on top, so arrangements come
+    // out in the same order as the counting branches
+    let mut stack: Vec<(usize, usize, String)> = vec![(0, 0, String::new())];
+    while let Some((g, p, prefix)) = stack.pop() {
+        if listing.len() >= cap {
+            break;
+        }
+        if table[g][p] == Some(0) {
+            continue;
+        }
+
+        if g == groupings.len() {
+            let mut arrangement = prefix;
+            arrangement.extend(std::iter::repeat_n('.', condition.len() - p));
+            // Drop the operational spring added as padding
+            arrangement.pop();
+            listing.push(arrangement);
+            continue;
+        }
+
+        let group_size = groupings[g];
+        if group_fits(&condition, &operational_before, p, group_size) {
+            let mut next = prefix.clone();
+            next.extend(std::iter::repeat_n('#', group_size));
+            next.push('.');
+            stack.push((g + 1, p + group_size + 1, next));
+        }
+        if condition[p] != Spring::Damaged {
+            stack.push((g, p + 1, prefix + "."));
         }
     }
 
-    if condition.len() < groupings.iter().sum::<usize>() + groupings.len() {
-        return 0;
+    (table[0][0], listing)
+}
+
+/// Row and column clues of a nonogram. Each line of the grid is a row of
+/// springs with damaged groups, so the arrangement counter doubles as the
+/// line solver.
+#[derive(Debug, Clone, PartialEq)]
+struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+}
+
+impl Nonogram {
+    /// Row clues, a blank line, then column clues, one line each. Group
+    /// sizes are separated by commas or spaces. Since blank lines separate
+    /// the blocks, an empty line is written as `0`.
+    fn parse(lines: &[String]) -> Result<Nonogram> {
+        let blocks: Vec<&[String]> = lines.split(|l| l.trim().is_empty()).filter(|b| !b.is_empty()).collect();
+        ensure!(blocks.len() == 2, "Expected row clues and column clues separated by a blank line, found {} blocks", blocks.len());
+
+        let parse_block = |block: &[String]| -> Result<Vec<Vec<usize>>> {
+            block
+                .iter()
+                .map(|l| {
+                    let clue = l
+                        .split([',', ' '])
+                        .filter(|x| !x.trim().is_empty())
+                        .map(|x| x.trim().parse::<usize>().with_context(|| format!("Bad clue '{}'", l)))
+                        .collect::<Result<Vec<usize>>>()?;
+                    if clue == [0] {
+                        return Ok(Vec::new());
+                    }
+                    ensure!(!clue.contains(&0), "Clue '{}' has a 0 alongside other groups", l);
+                    Ok(clue)
+                })
+                .collect()
+        };
+        let nonogram = Nonogram { rows: parse_block(blocks[0])?, columns: parse_block(blocks[1])? };
+
+        let (width, height) = (nonogram.columns.len(), nonogram.rows.len());
+        for (clues, size) in [(&nonogram.rows, width), (&nonogram.columns, height)] {
+            for clue in clues {
+                let needed = clue.iter().sum::<usize>() + clue.len().saturating_sub(1);
+                ensure!(needed <= size, "Clue {:?} doesn't fit in {} cells", clue, size);
+            }
+        }
+        let damaged_by_row: usize = nonogram.rows.iter().flatten().sum();
+        let damaged_by_column: usize = nonogram.columns.iter().flatten().sum();
+        ensure!(damaged_by_row == damaged_by_column, "Row clues fill {} cells but column clues fill {}", damaged_by_row, damaged_by_column);
+
+        Ok(nonogram)
     }
 
-    if let Some(cached) = cache[groupings.len() - 1][condition.len() - 1] {
-        return cached;
+    /// Number of solutions, stopping at `NONOGRAM_SOLUTION_CAP`, and the
+    /// first one found.
+    fn solve(&self) -> (usize, Option<Vec<Vec<Spring>>>) {
+        let grid = vec![vec![Spring::Unknown; self.columns.len()]; self.rows.len()];
+        let mut count = 0;
+        let mut first = None;
+        self.search(grid, &mut count, &mut first);
+        (count, first)
     }
 
-    let mut count = 0;
+    /// Propagates until nothing changes, then guesses the first unknown
+    /// cell both ways.
+    fn search(&self, mut grid: Vec<Vec<Spring>>, count: &mut usize, first: &mut Option<Vec<Vec<Spring>>>) {
+        if *count >= NONOGRAM_SOLUTION_CAP || !self.propagate(&mut grid) {
+            return;
+        }
+
+        let unknown = grid.iter().enumerate().find_map(|(y, row)| row.iter().position(|&s| s == Spring::Unknown).map(|x| (x, y)));
+        match unknown {
+            None => {
+                *count += 1;
+                first.get_or_insert(grid);
+            }
+            Some((x, y)) => {
+                for guess in [Spring::Damaged, Spring::Operational] {
+                    let mut attempt = grid.clone();
+                    attempt[y][x] = guess;
+                    self.search(attempt, count, first);
+                }
+            }
+        }
+    }
 
-    if condition[0] != Spring::Damaged {
-        count += recurse_arrangements(&condition[1..], groupings, cache);
+    /// Settles rows and columns in turn until a full pass changes nothing.
+    /// Returns false if some line can no longer be satisfied.
+    fn propagate(&self, grid: &mut [Vec<Spring>]) -> bool {
+        loop {
+            let mut changed = false;
+            for (y, clues) in self.rows.iter().enumerate() {
+                let Some(settled) = settle_line(&grid[y], clues) else {
+                    return false;
+                };
+                if settled != grid[y] {
+                    grid[y] = settled;
+                    changed = true;
+                }
+            }
+            for (x, clues) in self.columns.iter().enumerate() {
+                let column: Vec<Spring> = grid.iter().map(|row| row[x]).collect();
+                let Some(settled) = settle_line(&column, clues) else {
+                    return false;
+                };
+                if settled != column {
+                    for (row, s) in grid.iter_mut().zip(settled) {
+                        row[x] = s;
+                    }
+                    changed = true;
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
     }
+}
 
-    let group_size = groupings[0];
+/// Fills in every unknown cell of a line that only one value can take,
+/// found by counting arrangements with the cell fixed each way. `None` if
+/// the line has no arrangement at all.
+fn settle_line(line: &[Spring], clues: &[usize]) -> Option<Vec<Spring>> {
+    let possible = |springs: Vec<Spring>| find_arrangements(springs, clues.to_vec()) != Some(0);
+    if !possible(line.to_vec()) {
+        return None;
+    }
 
-    if !condition[..group_size].contains(&Spring::Operational) && condition[group_size] != Spring::Damaged {
-        count += recurse_arrangements(&condition[group_size + 1..], &groupings[1..], cache)
+    let mut settled = line.to_vec();
+    for (i, &spring) in line.iter().enumerate() {
+        if spring != Spring::Unknown {
+            continue;
+        }
+        let mut trial = line.to_vec();
+        trial[i] = Spring::Damaged;
+        let damaged = possible(trial.clone());
+        trial[i] = Spring::Operational;
+        let operational = possible(trial);
+        match (damaged, operational) {
+            (true, false) => settled[i] = Spring::Damaged,
+            (false, true) => settled[i] = Spring::Operational,
+            _ => {}
+        }
     }
+    Some(settled)
+}
 
-    cache[groupings.len() - 1][condition.len() - 1] = Some(count);
+/// Reference count that tries every assignment of the unknown springs and
+/// keeps those whose damaged runs match the groupings. Exponential, so only
+/// for short rows.
+fn brute_force_arrangements(condition: &[Spring], groupings: &[usize]) -> u128 {
+    let unknowns: Vec<usize> = condition.iter().positions(|&s| s == Spring::Unknown).collect();
+    let mut count = 0;
+    for mask in 0..1u64 << unknowns.len() {
+        let mut springs = condition.to_vec();
+        for (bit, &i) in unknowns.iter().enumerate() {
+            springs[i] = if mask & (1 << bit) != 0 { Spring::Damaged } else { Spring::Operational };
+        }
+        if damaged_groups(&springs) == groupings {
+            count += 1;
+        }
+    }
     count
 }
 
+fn damaged_groups(springs: &[Spring]) -> Vec<usize> {
+    springs
+        .split(|&s| s != Spring::Damaged)
+        .filter(|run| !run.is_empty())
+        .map(|run| run.len())
+        .collect()
+}
+
+/// Compares both the count from the arrangement table and the listing
+/// walked over it with the brute-force count for the same row.
+fn check_against_brute_force(condition: &[Spring], groupings: &[usize]) -> Result<()> {
+    let expected = brute_force_arrangements(condition, groupings);
+    let (count, listing) = list_arrangements(condition.to_vec(), groupings, usize::MAX);
+    let row = format!("{} {}", condition.iter().map(|&s| spring_char(s)).collect::<String>(), groupings.iter().join(","));
+    ensure!(
+        count == Some(expected),
+        "Row '{}' counted {:?} arrangements, brute force found {}",
+        row,
+        count,
+        expected
+    );
+    ensure!(
+        listing.len() as u128 == expected && listing.iter().all_unique(),
+        "Row '{}' listed {} arrangements, brute force found {}",
+        row,
+        listing.len(),
+        expected
+    );
+    Ok(())
+}
+
+/// Short row built by hiding some springs of a random solved row. Every
+/// fourth row gets random groupings instead, so impossible rows show up too.
+fn random_row(rng: &mut XorShift) -> (Vec<Spring>, Vec<usize>) {
+    let length = 1 + rng.below(14) as usize;
+    let solved: Vec<Spring> = (0..length)
+        .map(|_| if rng.below(2) == 0 { Spring::Damaged } else { Spring::Operational })
+        .collect();
+    let groupings = if rng.below(4) == 0 {
+        (0..rng.below(4)).map(|_| 1 + rng.below(4) as usize).collect()
+    } else {
+        damaged_groups(&solved)
+    };
+    let condition = solved
+        .into_iter()
+        .map(|s| if rng.below(3) == 0 { s } else { Spring::Unknown })
+        .collect();
+    (condition, groupings)
+}
+
+/// Small deterministic generator so verification runs are reproducible.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
+
+fn spring_char(s: Spring) -> char {
+    match s {
+        Spring::Operational => '.',
+        Spring::Damaged => '#',
+        Spring::Unknown => '?',
+    }
+}
+
 fn parse_springs(c: char) -> Spring {
     match c {
         '.' => Spring::Operational,
@@ -91,4 +459,76 @@ fn parse_springs(c: char) -> Spring {
         '?' => Spring::Unknown,
         _ => panic!("Unknown input character")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_count_example_rows() {
+        let rows = [("???.###", vec![1, 1, 3], 1), (".??..??...?##.", vec![1, 1, 3], 4), ("?###????????", vec![3, 2, 1], 10)];
+        for (row, groups, expected) in rows {
+            let condition: Vec<Spring> = row.chars().map(parse_springs).collect();
+            assert_eq!(find_arrangements(condition.clone(), groups.clone()), Some(expected));
+            assert_eq!(brute_force_arrangements(&condition, &groups), expected);
+        }
+    }
+
+    #[test]
+    fn can_count_heavily_unfolded_rows() {
+        let condition: Vec<Spring> = "???.###".chars().map(parse_springs).collect();
+        let unfold = 1000;
+        let unfolded = condition.iter().copied().chain([Spring::Unknown]).cycle().take(condition.len() * unfold + unfold - 1).collect();
+        assert_eq!(find_arrangements(unfolded, [1, 1, 3].repeat(unfold)), Some(1));
+
+        let condition: Vec<Spring> = "?###????????".chars().map(parse_springs).collect();
+        let unfolded = condition.iter().copied().chain([Spring::Unknown]).cycle().take(condition.len() * 50 + 49).collect();
+        assert_eq!(find_arrangements(unfolded, [3, 2, 1].repeat(50)), None);
+    }
+
+    #[test]
+    fn can_solve_nonogram() {
+        let clues = ["1", "3", "1 1", "", "2", "2", "2"].map(String::from);
+        let nonogram = Nonogram::parse(&clues).unwrap();
+        let (count, first) = nonogram.solve();
+        assert_eq!(count, 1);
+        let rendered: Vec<String> = first.unwrap().into_iter().map(|row| row.into_iter().map(spring_char).collect()).collect();
+        assert_eq!(rendered, vec![".#.", "###", "#.#"]);
+    }
+
+    #[test]
+    fn can_count_ambiguous_nonogram_solutions() {
+        let clues = ["1", "1", "", "1", "1"].map(String::from);
+        assert_eq!(Nonogram::parse(&clues).unwrap().solve().0, 2);
+        let impossible = ["2", "0", "", "2", "0"].map(String::from);
+        assert_eq!(Nonogram::parse(&impossible).unwrap().solve().0, 0);
+        let mismatched = ["2", "", "1", "0"].map(String::from);
+        assert!(Nonogram::parse(&mismatched).is_err());
+    }
+
+    #[test]
+    fn can_solve_nonogram_with_empty_lines() {
+        let clues = ["1,1", "0", "2", "", "1", "1", "1,1"].map(String::from);
+        let nonogram = Nonogram::parse(&clues).unwrap();
+        assert_eq!(nonogram.rows[1], Vec::<usize>::new());
+        let (count, first) = nonogram.solve();
+        assert_eq!(count, 1);
+        let rendered: Vec<String> = first.unwrap().into_iter().map(|row| row.into_iter().map(spring_char).collect()).collect();
+        assert_eq!(rendered, vec!["#.#", "...", ".##"]);
+    }
+
+    #[test]
+    fn rejects_zero_inside_a_clue() {
+        let clues = ["1 0 1", "0", "2", "", "1", "1", "1,1"].map(String::from);
+        assert!(Nonogram::parse(&clues).is_err());
+    }
+
+    #[test]
+    fn table_count_matches_brute_force_on_random_rows() {
+        let mut rng = XorShift(VERIFY_SEED);
+        for _ in 0..VERIFY_RANDOM_ROWS {
+            let (condition, groupings) = random_row(&mut rng);
+            check_against_brute_force(&condition, &groupings).unwrap();
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use std::io::BufRead;
+use std::{io::BufRead, collections::{BTreeMap, HashSet}};
 
 use crate::helpers::{self, Args};
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use log::info;
 
+const DEFAULT_BAG: &str = "red=12,green=13,blue=14";
+
 pub fn run(args: &mut Args) -> Result<()> {
     info!(target: "Day 2", "Running...");
     info!(target: "Day 2", "Parsing input from file");
@@ -13,19 +15,30 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 2", "Solving...");
 
-    let mut game_counter = 0;
-    let mut winning_games_part1 = 0;
-    let mut winning_games_part2 = 0;
+    let bag = parse_bag(args.bag.as_deref().unwrap_or(DEFAULT_BAG)).with_context(|| "Invalid bag contents")?;
+
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut winning_games_part1: u64 = 0;
+    let mut winning_games_part2: u64 = 0;
 
-    for line in reader.lines() {
-        game_counter += 1;
-        let game = CubeGame::from_line(line.with_context(|| "Error reading line from buffer")?);
+    for (idx, line) in reader.lines().enumerate() {
+        let game = CubeGame::from_line(line.with_context(|| "Error reading line from buffer")?)
+            .with_context(|| format!("Bad game on line {}", idx + 1))?;
+        ensure!(seen.insert(game.id), "Game {} on line {} was already played", game.id, idx + 1);
         
-        if game.is_valid() {
-            winning_games_part1 += game_counter;
+        if game.is_valid(&bag) {
+            winning_games_part1 += game.id as u64;
         }
 
         winning_games_part2 += game.min_cubes();
+
+        if args.explain {
+            println!("{}", game.explain(&bag));
+        }
+    }
+
+    if args.explain {
+        return Ok(());
     }
 
     println!{"Part 1 Sum: {}", winning_games_part1};
@@ -34,115 +47,189 @@ pub fn run(args: &mut Args) -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug)]
-struct CubeGameRound {
-    red: u8,
-    green: u8,
-    blue: u8,
-}
-
-impl CubeGameRound {
-    fn new(r: u8, g: u8, b: u8) -> CubeGameRound {
-        CubeGameRound{red: r, green: g, blue: b}
+/// Bag contents as `color=count` pairs separated by commas.
+fn parse_bag(value: &str) -> Result<BTreeMap<String, u32>> {
+    let mut bag: BTreeMap<String, u32> = BTreeMap::new();
+    for entry in value.split(',') {
+        let (color, count) = entry.split_once('=').with_context(|| format!("Expected color=count but found '{}'", entry))?;
+        let count = count.trim().parse::<u32>().with_context(|| format!("Bad count for {}", color.trim()))?;
+        ensure!(bag.insert(color.trim().to_string(), count).is_none(), "Color {} listed twice", color.trim());
     }
+    Ok(bag)
 }
 
-impl PartialEq for CubeGameRound {
-    fn eq(&self, other: &Self) -> bool {
-        self.red == other.red && self.green == other.green && self.blue == other.blue
-    }
+/// Cubes shown in one handful, by color, along with the colors in the
+/// order the input first lists them.
+#[derive(Debug, PartialEq)]
+struct CubeGameRound {
+    cubes: BTreeMap<String, u32>,
+    order: Vec<String>,
 }
 
 #[derive(Debug)]
 struct CubeGame{
+    id: u32,
     rounds: Vec<CubeGameRound>,
 }
 
 impl CubeGame {
-    fn new(r: Vec<CubeGameRound>) -> CubeGame {
-        CubeGame {rounds: r}
+    fn new(id: u32, r: Vec<CubeGameRound>) -> CubeGame {
+        CubeGame {id, rounds: r}
     }
 
-    fn from_line(l: String) -> CubeGame{
-        CubeGame::new(CubeGame::parse(l))
+    fn from_line(l: String) -> Result<CubeGame> {
+        let (id, rounds) = CubeGame::parse(l)?;
+        Ok(CubeGame::new(id, rounds))
     }
 
-    fn parse(value: String) -> Vec<CubeGameRound> {
+    fn parse(value: String) -> Result<(u32, Vec<CubeGameRound>)> {
         let v: Vec<&str> = value.split(':').collect();
+        ensure!(v.len() == 2, "Expected 'Game N: ...' but found '{}'", value);
+        let id = v[0]
+            .trim()
+            .strip_prefix("Game")
+            .with_context(|| format!("Expected 'Game N' but found '{}'", v[0]))?
+            .trim()
+            .parse::<u32>()
+            .with_context(|| format!("Bad game id in '{}'", v[0]))?;
         let rs: Vec<&str> = v[1].split(';').collect();
         let mut rounds: Vec<CubeGameRound> = Vec::new();
         for r in rs {
-            let mut red: u8 = 0;
-            let mut green: u8 = 0;
-            let mut blue: u8 = 0;
+            let mut cubes: BTreeMap<String, u32> = BTreeMap::new();
+            let mut order: Vec<String> = Vec::new();
             let colors: Vec<&str> = r.split(',').collect();
             for color in colors {
-                let entry: Vec<&str> = color.trim_start().split(' ').collect();
-                match entry[1] {
-                    "red" => red = entry[0].parse::<u8>().unwrap(),
-                    "green" => green = entry[0].parse::<u8>().unwrap(),
-                    "blue" => blue = entry[0].parse::<u8>().unwrap(),
-                    _ => panic!("Unrecoverable error: unexpected color {}", entry[1]),
-                };
+                let entry: Vec<&str> = color.split_whitespace().collect();
+                ensure!(entry.len() == 2, "Expected 'count color' but found '{}'", color.trim());
+                let count = entry[0].parse::<u32>().with_context(|| format!("Bad count for {}", entry[1]))?;
+                if !cubes.contains_key(entry[1]) {
+                    order.push(entry[1].to_string());
+                }
+                *cubes.entry(entry[1].to_string()).or_insert(0) += count;
             }
-            rounds.push(CubeGameRound::new(red, green, blue));
+            rounds.push(CubeGameRound { cubes, order });
         }
-        rounds
+        Ok((id, rounds))
     }
 
-    fn is_valid(&self) -> bool {
-        const RED_MAX: u8 = 12;
-        const GREEN_MAX: u8 = 13;
-        const BLUE_MAX: u8 = 14;
-        let mut valid = true;
+    fn is_valid(&self, bag: &BTreeMap<String, u32>) -> bool {
+        self.first_violation(bag).is_none()
+    }
 
-        for round in &self.rounds {
-            if round.red > RED_MAX || round.green > GREEN_MAX || round.blue > BLUE_MAX {
-                valid = false;
+    /// First round (numbered from 1) showing more cubes of a color than the
+    /// bag holds, with the first such color as listed on the line and its count.
+    fn first_violation(&self, bag: &BTreeMap<String, u32>) -> Option<(usize, &str, u32)> {
+        for (i, round) in self.rounds.iter().enumerate() {
+            for color in &round.order {
+                let count = round.cubes[color];
+                if count > *bag.get(color).unwrap_or(&0) {
+                    return Some((i + 1, color, count));
+                }
             }
         }
-        valid
+        None
     }
-    fn min_cubes(&self) -> i32 {
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
+
+    /// Smallest bag that makes the game possible: the most cubes of each
+    /// color the game shows. The bag's own limits play no part.
+    fn min_bag(&self) -> BTreeMap<&str, u32> {
+        let mut minimum: BTreeMap<&str, u32> = BTreeMap::new();
         for round in &self.rounds {
-            if round.red > red {
-                red = round.red;
-            } 
-            if round.green > green {
-                green = round.green;
-            }
-            if round.blue > blue {
-                blue = round.blue;
+            for (color, &count) in &round.cubes {
+                let entry = minimum.entry(color.as_str()).or_insert(0);
+                if count > *entry {
+                    *entry = count;
+                }
             }
         }
-        red as i32 * green as i32 * blue as i32
+        minimum
+    }
+
+    /// Power of the smallest bag that makes the game possible.
+    fn min_cubes(&self) -> u64 {
+        self.min_bag().values().map(|&count| count as u64).product()
+    }
+
+    fn explain(&self, bag: &BTreeMap<String, u32>) -> String {
+        let verdict = match self.first_violation(bag) {
+            Some((round, color, count)) => format!(
+                "impossible, round {} shows {} {} but the bag holds {}",
+                round,
+                count,
+                color,
+                bag.get(color).unwrap_or(&0)
+            ),
+            None => String::from("possible"),
+        };
+        let minimum = self.min_bag().iter().map(|(color, count)| format!("{}={}", color, count)).collect::<Vec<String>>().join(",");
+        format!("Game {}: {}; minimum bag {}, power {}", self.id, verdict, minimum, self.min_cubes())
     }
 
 }
 
 impl PartialEq for CubeGame {
     fn eq(&self, other: &Self) -> bool {
-        self.rounds == other.rounds
+        self.id == other.id && self.rounds == other.rounds
     }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
     /* cSpell:disable */
+    fn round(cubes: &[(&str, u32)]) -> CubeGameRound {
+        CubeGameRound {
+            cubes: cubes.iter().map(|&(color, count)| (color.to_string(), count)).collect(),
+            order: cubes.iter().map(|&(color, _)| color.to_string()).collect(),
+        }
+    }
+
     #[test]
     fn can_parse_line_into_game() {
         let input1 = String::from("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green");
-        let test1 = CubeGame::from_line(input1);
+        let test1 = CubeGame::from_line(input1).unwrap();
         assert_eq!(test1, CubeGame {
+            id: 1,
             rounds: vec! [
-                CubeGameRound {red: 4, green: 0, blue: 3},
-                CubeGameRound {red: 1, green: 2, blue: 6},
-                CubeGameRound {red: 0, green: 2, blue: 0},
+                round(&[("blue", 3), ("red", 4)]),
+                round(&[("red", 1), ("green", 2), ("blue", 6)]),
+                round(&[("green", 2)]),
             ]
         });
     }
+
+    #[test]
+    fn can_play_with_extra_colors() {
+        let bag = parse_bag("red=12,green=13,blue=14,yellow=5").unwrap();
+        let game = CubeGame::from_line(String::from("Game 1: 3 blue, 4 yellow; 1 red, 2 green")).unwrap();
+        assert!(game.is_valid(&bag));
+        assert_eq!(game.min_cubes(), 24);
+        let game = CubeGame::from_line(String::from("Game 2: 6 yellow, 1 red, 1 green, 1 blue")).unwrap();
+        assert!(!game.is_valid(&bag));
+        assert!(!game.is_valid(&parse_bag(DEFAULT_BAG).unwrap()));
+    }
+
+    #[test]
+    fn min_bag_ignores_colors_the_game_never_shows() {
+        let game = CubeGame::from_line(String::from("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green")).unwrap();
+        assert_eq!(game.min_bag(), BTreeMap::from([("blue", 6), ("green", 2), ("red", 4)]));
+        assert_eq!(game.min_cubes(), 48);
+    }
+
+    #[test]
+    fn reports_violations_in_input_order() {
+        let bag = parse_bag("red=1,green=13,blue=1").unwrap();
+        let game = CubeGame::from_line(String::from("Game 1: 2 green; 5 red, 5 blue")).unwrap();
+        assert_eq!(game.first_violation(&bag), Some((2, "red", 5)));
+        let game = CubeGame::from_line(String::from("Game 2: 5 blue, 5 red")).unwrap();
+        assert_eq!(game.first_violation(&bag), Some((1, "blue", 5)));
+    }
+
+    #[test]
+    fn can_parse_game_id_and_large_counts() {
+        let game = CubeGame::from_line(String::from("Game 42: 300 red, 1 blue")).unwrap();
+        assert_eq!(game.id, 42);
+        assert_eq!(game.rounds, vec![round(&[("red", 300), ("blue", 1)])]);
+        assert!(CubeGame::from_line(String::from("Round 1: 3 red")).is_err());
+    }
     
-}
\ No newline at end of file
+}
@@ -1,8 +1,10 @@
-use std::{io::BufRead, collections::HashSet};
+use std::{io::BufRead, collections::{BTreeSet, HashSet}};
 
 use crate::helpers::{self, Args};
 use anyhow::{Context, Result};
 use array2d::Array2D;
+use colored::Colorize;
+use itertools::Itertools;
 use log::info;
 
 pub fn run(args: &mut Args) -> Result<()> {
@@ -14,37 +16,49 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 3", "Solving...");
 
-    let mut s = Array2D::filled_with('.', Y_MAX, X_MAX);
-    let mut p: Vec<PartNumber> = Vec::new();
-    let mut r: Vec<Point> = Vec::new();
+    let lines = reader.lines().collect::<Result<Vec<String>, _>>().with_context(|| "Couldn't read line")?;
+    let engine = Engine::parse(lines);
 
-    for (y_pos, line) in reader.lines().enumerate() {
-        let mut l: Vec<Point> = Vec::new();
-        let mut n: String = String::new();
-        for (x_pos, value) in line.unwrap().chars().enumerate() {
-                if value == '*' {
-                    r.push(Point::new(x_pos, y_pos));
+    if args.render {
+        if args.html {
+            println!("{}", engine.render_html());
+        } else {
+            for row in engine.cell_kinds() {
+                for (c, kind) in row {
+                    let text = c.to_string();
+                    let colored = match kind {
+                        CellKind::ValidPart => text.green(),
+                        CellKind::OrphanPart => text.red(),
+                        CellKind::Gear => text.yellow().bold(),
+                        CellKind::Symbol => text.cyan(),
+                        CellKind::Empty => text.dimmed(),
+                    };
+                    print!("{}", colored);
                 }
-                if is_number(value) {
-                    n.push(value);
-                    l.push(Point::new(x_pos, y_pos));
-                } else if !l.is_empty() {
-                    p.push(PartNumber::new(n.clone(), n.parse::<i32>().unwrap(), l.clone()));
-                    n.clear();
-                    l.clear();
-                }
-            s[(x_pos, y_pos)] = value;
+                println!();
+            }
         }
-        if !l.is_empty() {
-            p.push(PartNumber::new(n.clone(), n.parse::<i32>().unwrap(), l.clone()));
-            n.clear();
-            l.clear();
+        return Ok(());
+    }
+
+    if args.table || args.symbol.is_some() || args.min_parts.is_some() {
+        let matches = engine.find_symbols(args.symbol, args.min_parts.unwrap_or(0));
+        if args.table {
+            println!("symbol,x,y,part_count,parts");
         }
+        for sp in matches {
+            let parts = sp.parts.iter().map(|&id| engine.part_numbers[id].value).join(if args.table { ";" } else { ", " });
+            if args.table {
+                println!("{},{},{},{},{}", sp.symbol, sp.location.x, sp.location.y, sp.parts.len(), parts);
+            } else {
+                println!("'{}' at ({}, {}) touches {} parts: {}", sp.symbol, sp.location.x, sp.location.y, sp.parts.len(), parts);
+            }
+        }
+        return Ok(());
     }
 
-    let engine = Engine::new(s, p);
     println!("Part 1: Sum of Part Numbers = {}", engine.calculate_sum_of_part_numbers());
-    println!("Part 2: Sum of Gear Ratios = {}", engine.calculate_sum_of_gear_ratios(r));
+    println!("Part 2: Sum of Gear Ratios = {}", engine.calculate_sum_of_gear_ratios());
 
     Ok(())
 }
@@ -64,22 +78,103 @@ fn is_period(c: char) -> bool {
     c == '.'
 }
 
-const X_MAX: usize = 140;
-const Y_MAX: usize = 140;
-
 struct Engine {
     schematic: Array2D<char>,
     part_numbers: Vec<PartNumber>,
+    symbols: Vec<(char, Point)>,
+    /// Index into `part_numbers` of the part covering each cell, if any
+    part_index: Array2D<Option<usize>>,
+}
+
+/// How a cell is drawn when rendering the schematic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellKind {
+    Empty,
+    ValidPart,
+    OrphanPart,
+    Symbol,
+    Gear,
+}
+
+impl CellKind {
+    fn css_class(&self) -> &'static str {
+        match self {
+            CellKind::Empty => "empty",
+            CellKind::ValidPart => "valid",
+            CellKind::OrphanPart => "orphan",
+            CellKind::Symbol => "symbol",
+            CellKind::Gear => "gear",
+        }
+    }
+}
+
+/// A symbol in the schematic and the distinct part numbers touching it, as
+/// indexes into `Engine::part_numbers`.
+#[derive(Debug)]
+struct SymbolParts {
+    symbol: char,
+    location: Point,
+    parts: Vec<usize>,
 }
 
 impl Engine {
-    fn new(schematic: Array2D<char>, part_numbers: Vec<PartNumber>) -> Engine {
+    /// Reads the schematic, collecting part numbers as runs of digits and
+    /// every other non-period character as a symbol.
+    fn parse(lines: Vec<String>) -> Engine {
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let height = lines.len();
+
+        let mut s = Array2D::filled_with('.', width, height);
+        let mut p: Vec<PartNumber> = Vec::new();
+        let mut r: Vec<(char, Point)> = Vec::new();
+
+        for (y_pos, line) in lines.into_iter().enumerate() {
+            let mut l: Vec<Point> = Vec::new();
+            let mut n: String = String::new();
+            for (x_pos, value) in line.chars().enumerate() {
+                if is_symbol(value) {
+                    r.push((value, Point::new(x_pos, y_pos)));
+                }
+                if is_number(value) {
+                    n.push(value);
+                    l.push(Point::new(x_pos, y_pos));
+                } else if !l.is_empty() {
+                    p.push(PartNumber::new(n.clone(), n.parse::<i32>().unwrap(), l.clone()));
+                    n.clear();
+                    l.clear();
+                }
+                s[(x_pos, y_pos)] = value;
+            }
+            if !l.is_empty() {
+                p.push(PartNumber::new(n.clone(), n.parse::<i32>().unwrap(), l.clone()));
+                n.clear();
+                l.clear();
+            }
+        }
+
+        Engine::new(s, p, r)
+    }
+
+    fn new(schematic: Array2D<char>, part_numbers: Vec<PartNumber>, symbols: Vec<(char, Point)>) -> Engine {
+        let mut part_index = Array2D::filled_with(None, schematic.num_rows(), schematic.num_columns());
+        for (id, part_number) in part_numbers.iter().enumerate() {
+            for location in &part_number.locations {
+                part_index[(location.x, location.y)] = Some(id);
+            }
+        }
+
         Engine {
             schematic,
             part_numbers,
+            symbols,
+            part_index,
         }
     }
 
+    fn neighbors(&self, point: &Point) -> Vec<(usize, usize)> {
+        point.find_neighbors(self.schematic.num_rows(), self.schematic.num_columns())
+    }
+
     fn calculate_sum_of_part_numbers(&self) -> i32 {
         let mut total: i32 = 0;
         for part_number in &self.part_numbers {
@@ -90,36 +185,109 @@ impl Engine {
         total
     }
 
-    fn calculate_sum_of_gear_ratios(&self, ratios: Vec<Point>) -> i32 {
+    fn calculate_sum_of_gear_ratios(&self) -> i32 {
         let mut total: i32 = 0;
-        let mut part_numbers: HashSet<PartNumber> = HashSet::new();
-        for point in ratios {
-            let neighbors: Vec<(usize, usize)> = point.find_neighbors();
-            for neighbor in neighbors {
-                if is_number(self.schematic[neighbor]) {
-                    for p in &self.part_numbers {
-                        let test_point = Point::from_tuple(neighbor);
-                        if p.in_location(&test_point) {
-                            part_numbers.insert(p.clone());
-                        }
-                    }
-                }
-            }
-            if part_numbers.len() == 2 {
+        for gear in self.symbol_parts() {
+            if gear.symbol == '*' && gear.parts.len() == 2 {
                 let mut ratio = 1;
-                for part in &part_numbers {
-                    ratio *= part.value;
+                for &id in &gear.parts {
+                    ratio *= self.part_numbers[id].value;
                 }
                 total += ratio
             }
-            part_numbers.clear();
         }
         total
     }
 
+    /// Every symbol with the part numbers adjacent to it, in reading order.
+    fn symbol_parts(&self) -> Vec<SymbolParts> {
+        let mut relation: Vec<SymbolParts> = Vec::new();
+        for (symbol, point) in &self.symbols {
+            let ids: BTreeSet<usize> = self
+                .neighbors(point)
+                .into_iter()
+                .filter_map(|neighbor| self.part_index[neighbor])
+                .collect();
+            relation.push(SymbolParts {
+                symbol: *symbol,
+                location: *point,
+                parts: ids.into_iter().collect(),
+            });
+        }
+        relation
+    }
+
+    /// Symbols matching `symbol` (any if `None`) that touch at least
+    /// `min_parts` part numbers.
+    fn find_symbols(&self, symbol: Option<char>, min_parts: usize) -> Vec<SymbolParts> {
+        self.symbol_parts()
+            .into_iter()
+            .filter(|sp| symbol.is_none_or(|c| sp.symbol == c))
+            .filter(|sp| sp.parts.len() >= min_parts)
+            .collect()
+    }
+
+    /// Every cell row by row with how it should be drawn: digits by whether
+    /// their part number touches a symbol, and `*` as a gear when it touches
+    /// exactly two parts.
+    fn cell_kinds(&self) -> Vec<Vec<(char, CellKind)>> {
+        let valid: Vec<bool> = self.part_numbers.iter().map(|p| self.is_valid_part_number(p)).collect();
+        let gears: HashSet<Point> = self
+            .symbol_parts()
+            .into_iter()
+            .filter(|sp| sp.symbol == '*' && sp.parts.len() == 2)
+            .map(|sp| sp.location)
+            .collect();
+
+        (0..self.schematic.num_columns())
+            .map(|y| {
+                (0..self.schematic.num_rows())
+                    .map(|x| {
+                        let c = self.schematic[(x, y)];
+                        let kind = match self.part_index[(x, y)] {
+                            Some(id) if valid[id] => CellKind::ValidPart,
+                            Some(_) => CellKind::OrphanPart,
+                            None if gears.contains(&Point::new(x, y)) => CellKind::Gear,
+                            None if is_symbol(c) => CellKind::Symbol,
+                            None => CellKind::Empty,
+                        };
+                        (c, kind)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn render_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<style>\n\
+             body { background: #0f0f23; color: #cccccc; }\n\
+             .empty { color: #555555; }\n\
+             .valid { color: #00cc00; }\n\
+             .orphan { color: #ff4444; }\n\
+             .symbol { color: #44cccc; }\n\
+             .gear { color: #ffff66; font-weight: bold; }\n\
+             </style>\n</head>\n<body>\n<pre>\n",
+        );
+        for row in self.cell_kinds() {
+            for (c, kind) in row {
+                let text = match c {
+                    '<' => String::from("&lt;"),
+                    '>' => String::from("&gt;"),
+                    '&' => String::from("&amp;"),
+                    _ => c.to_string(),
+                };
+                html.push_str(&format!("<span class=\"{}\">{}</span>", kind.css_class(), text));
+            }
+            html.push('\n');
+        }
+        html.push_str("</pre>\n</body>\n</html>");
+        html
+    }
+
     fn is_valid_part_number(&self, part_number: &PartNumber) -> bool {
         for location in &part_number.locations {
-            for neighbor in location.find_neighbors() {
+            for neighbor in self.neighbors(location) {
                 if is_symbol(self.schematic[neighbor]) {
                     return true;
                 }
@@ -144,13 +312,6 @@ impl PartNumber {
             locations,
         }
     }
-
-    fn in_location(&self, point: &Point) -> bool {
-        if self.locations.contains(point) {
-            return true;
-        }
-        false
-    }
 }
 
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
@@ -164,21 +325,89 @@ impl Point {
         Point {x, y}
     }
 
-    fn from_tuple(t: (usize, usize)) -> Point {
-        Point {x: t.0, y: t.1 }
-    }
-
-    fn find_neighbors(&self) -> Vec<(usize, usize)> {
+    fn find_neighbors(&self, x_max: usize, y_max: usize) -> Vec<(usize, usize)> {
         let mut neighbors: Vec<(usize, usize)> = Vec::new();
         for y in -1..=1 {
-            if (self.y as i32) + y >= 0 && (self.y as i32) + y < Y_MAX as i32 {
+            if (self.y as i64) + y >= 0 && (self.y as i64) + y < y_max as i64 {
                 for x in -1..=1 {
-                    if (self.x as i32) + x >= 0 && (self.x as i32) + x < X_MAX as i32 {
-                        neighbors.push(((self.x as i32 + x) as usize, (self.y as i32 + y) as usize));
+                    if (self.x as i64) + x >= 0 && (self.x as i64) + x < x_max as i64 {
+                        neighbors.push(((self.x as i64 + x) as usize, (self.y as i64 + y) as usize));
                     }
                 }
             }
         }
         neighbors
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 10] = [
+        "467..114..",
+        "...*......",
+        "..35..633.",
+        "......#...",
+        "617*......",
+        ".....+.58.",
+        "..592.....",
+        "......755.",
+        "...$.*....",
+        ".664.598..",
+    ];
+
+    fn example() -> Engine {
+        Engine::parse(EXAMPLE.iter().map(|l| l.to_string()).collect())
+    }
+
+    fn values(engine: &Engine, parts: &[usize]) -> Vec<i32> {
+        parts.iter().map(|&id| engine.part_numbers[id].value).collect()
+    }
+
+    #[test]
+    fn can_sum_example() {
+        let engine = example();
+        assert_eq!(engine.calculate_sum_of_part_numbers(), 4361);
+        assert_eq!(engine.calculate_sum_of_gear_ratios(), 467835);
+    }
+
+    #[test]
+    fn can_relate_symbols_to_parts() {
+        let engine = example();
+        let gears: Vec<(Point, Vec<i32>)> = engine
+            .symbol_parts()
+            .into_iter()
+            .filter(|sp| sp.symbol == '*' && sp.parts.len() == 2)
+            .map(|sp| (sp.location, values(&engine, &sp.parts)))
+            .collect();
+        assert_eq!(gears, vec![(Point::new(3, 1), vec![467, 35]), (Point::new(5, 8), vec![755, 598])]);
+
+        let stars: Vec<Point> = engine.find_symbols(Some('*'), 0).iter().map(|sp| sp.location).collect();
+        assert_eq!(stars, vec![Point::new(3, 1), Point::new(3, 4), Point::new(5, 8)]);
+        let busy: Vec<Point> = engine.find_symbols(Some('*'), 2).iter().map(|sp| sp.location).collect();
+        assert_eq!(busy, vec![Point::new(3, 1), Point::new(5, 8)]);
+        let any: Vec<char> = engine.find_symbols(None, 2).iter().map(|sp| sp.symbol).collect();
+        assert_eq!(any, vec!['*', '*']);
+        assert!(engine.find_symbols(None, 3).is_empty());
+    }
+
+    #[test]
+    fn can_classify_cells() {
+        let kinds = example().cell_kinds();
+        let kind_at = |x: usize, y: usize| kinds[y][x].1;
+        for x in 5..8 {
+            assert_eq!(kind_at(x, 0), CellKind::OrphanPart);
+        }
+        for x in 7..9 {
+            assert_eq!(kind_at(x, 5), CellKind::OrphanPart);
+        }
+        assert_eq!(kind_at(0, 0), CellKind::ValidPart);
+        assert_eq!(kind_at(3, 1), CellKind::Gear);
+        assert_eq!(kind_at(5, 8), CellKind::Gear);
+        assert_eq!(kind_at(3, 4), CellKind::Symbol);
+        assert_eq!(kind_at(6, 3), CellKind::Symbol);
+        assert_eq!(kind_at(4, 0), CellKind::Empty);
+        let orphans = kinds.iter().flatten().filter(|&&(_, kind)| kind == CellKind::OrphanPart).count();
+        assert_eq!(orphans, 5);
+    }
+}
@@ -1,7 +1,7 @@
-use std::{io::BufRead, collections::HashMap};
+use std::{io::BufRead, collections::HashSet};
 
 use crate::helpers::{self, Args};
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use log::info;
 
 pub fn run(args: &mut Args) -> Result<()> {
@@ -13,76 +13,160 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 4", "Solving...");
 
-    let mut total_part1: u32 = 0;
-    let mut total_part2: u32 = 0;
-
-    let mut game: HashMap<u32, u32> = HashMap::new();
+    let mut total_part1: u64 = 0;
+    let mut winners: Vec<u32> = Vec::new();
+    let mut points: Vec<u64> = Vec::new();
 
     for (idx, line) in reader.lines().enumerate() {
         let mut card = Card::new();
-        card.read(line.with_context(|| "Error reading line")?);
+        card.read(&line.with_context(|| "Error reading line")?)
+            .with_context(|| format!("Bad card on line {}", idx + 1))?;
+        ensure!(
+            card.id as usize == winners.len() + 1,
+            "Card {} on line {} is out of order, expected card {}",
+            card.id,
+            idx + 1,
+            winners.len() + 1
+        );
         let number_of_winners = card.check_number_of_winners();
 
-        total_part1 += card.score_winner(number_of_winners);
-        
-        let k = idx as u32 + 1;
+        let score = card
+            .score_winner(number_of_winners)
+            .with_context(|| format!("Points of card {} overflow u64", card.id))?;
+        total_part1 = total_part1.checked_add(score).with_context(|| "Total points overflow u64")?;
+        winners.push(number_of_winners);
+        points.push(score);
+    }
 
-        game.entry(k).and_modify(|v| *v += 1).or_insert(1);
+    let copies = cascade_copies(&winners)?;
 
-        for _ in 0..*game.get(&k).unwrap() {
-            for j in 1..number_of_winners + 1 {
-                game.entry(k + j).and_modify(|v| *v += 1).or_insert(1);
-            }
+    if args.explain {
+        println!("card,matches,points,copies");
+        for (i, ((matches, score), count)) in winners.iter().zip(&points).zip(&copies).enumerate() {
+            println!("{},{},{},{}", i + 1, matches, score, count);
         }
+        return Ok(());
     }
-    
-    for v in game.values() {
-        total_part2 += v;
-    }
+
+    let total_part2 = copies
+        .iter()
+        .try_fold(0u64, |total, &count| total.checked_add(count))
+        .with_context(|| "Total number of cards overflows u64")?;
     println!("Part1: {}", total_part1);
-    println!("Part1: {}", total_part2);
+    println!("Part2: {}", total_part2);
    
     Ok(())
 }
 
+/// Final number of copies of each card. Every copy of a card wins one copy
+/// of each of the next `winners` cards, so a card's count is added to those
+/// cards once rather than looping over its copies one by one. Counts grow
+/// exponentially, so overflowing u64 is reported instead of wrapping.
+fn cascade_copies(winners: &[u32]) -> Result<Vec<u64>> {
+    let mut copies: Vec<u64> = vec![1; winners.len()];
+    for (i, &number_of_winners) in winners.iter().enumerate() {
+        let end = (i + 1 + number_of_winners as usize).min(winners.len());
+        for j in i + 1..end {
+            copies[j] = copies[j]
+                .checked_add(copies[i])
+                .with_context(|| format!("Copies of card {} overflow u64", j + 1))?;
+        }
+    }
+    Ok(copies)
+}
+
 #[derive(Debug)]
 struct Card {
-    winning_numbers: Vec<String>,
-    my_numbers: Vec<String>
+    id: u32,
+    winning_numbers: HashSet<u32>,
+    my_numbers: HashSet<u32>
 }
 
 impl Card {
     fn new() -> Card {
         Card {
-            winning_numbers: Vec::new(), 
-            my_numbers: Vec::new()
+            id: 0,
+            winning_numbers: HashSet::new(),
+            my_numbers: HashSet::new()
         }
     }
 
-    fn read(&mut self, input: String) {
-        let numbers: Vec<&str> = (input.split(':').collect::<Vec<&str>>()[1]).split('|').collect();
-        let winning_numbers: Vec<String> = numbers[0].split_whitespace().map(|x: &str| x.to_string()).collect();
-        let my_numbers: Vec<String> = numbers[1].split_whitespace().map(|x: &str| x.to_string()).collect();
-        self.winning_numbers = winning_numbers;
-        self.my_numbers = my_numbers;
+    fn read(&mut self, input: &str) -> Result<()> {
+        let (label, numbers) = input.split_once(':').with_context(|| "Missing ':' after card id")?;
+        let id = label
+            .strip_prefix("Card")
+            .with_context(|| format!("Expected 'Card N' but found '{}'", label))?;
+        self.id = id.trim().parse::<u32>().with_context(|| format!("Bad card id '{}'", id.trim()))?;
+
+        let (winning_numbers, my_numbers) = numbers.split_once('|').with_context(|| "Missing '|' between number lists")?;
+        self.winning_numbers = Card::read_numbers(winning_numbers).with_context(|| "Bad winning numbers")?;
+        self.my_numbers = Card::read_numbers(my_numbers).with_context(|| "Bad numbers you have")?;
+        Ok(())
     }
 
-    fn check_number_of_winners(&self) -> u32 {
-        let mut num: u32 = 0;
-
-        for my_number in &self.my_numbers {
-            if self.winning_numbers.contains(my_number) {
-                num += 1;
-            }
+    fn read_numbers(input: &str) -> Result<HashSet<u32>> {
+        let mut numbers: HashSet<u32> = HashSet::new();
+        for value in input.split_whitespace() {
+            let number = value.parse::<u32>().with_context(|| format!("'{}' is not a number", value))?;
+            ensure!(numbers.insert(number), "Duplicate number {}", number);
         }
-        num
+        Ok(numbers)
+    }
+
+    fn check_number_of_winners(&self) -> u32 {
+        self.my_numbers.intersection(&self.winning_numbers).count() as u32
     }
 
-    fn score_winner(&self, num: u32) -> u32 {
-        let base: u32 = 2;
+    /// Points double with each match after the first, so `None` once that
+    /// no longer fits in a u64.
+    fn score_winner(&self, num: u32) -> Option<u64> {
+        let base: u64 = 2;
         if num == 0 {
-            return 0;
+            return Some(0);
         }
-        base.pow(num - 1)
+        base.checked_pow(num - 1)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_card() {
+        let mut card = Card::new();
+        card.read("Card  12: 41 48 83 86 17 | 83 86  6 31 17  9 48 53").unwrap();
+        assert_eq!(card.id, 12);
+        assert_eq!(card.winning_numbers, HashSet::from([41, 48, 83, 86, 17]));
+        assert_eq!(card.check_number_of_winners(), 4);
+    }
+
+    #[test]
+    fn rejects_malformed_cards() {
+        assert!(Card::new().read("Card 1 41 48 | 83 86").is_err());
+        assert!(Card::new().read("Card 1: 41 48 83 86").is_err());
+        assert!(Card::new().read("Card 1: 41 41 | 83 86").is_err());
+        assert!(Card::new().read("Game 1: 41 48 | 83 86").is_err());
+    }
+
+    #[test]
+    fn reports_overflowing_points() {
+        let card = Card::new();
+        assert_eq!(card.score_winner(0), Some(0));
+        assert_eq!(card.score_winner(4), Some(8));
+        assert_eq!(card.score_winner(40), Some(1 << 39));
+        assert_eq!(card.score_winner(65), None);
+    }
+
+    #[test]
+    fn can_cascade_copies() {
+        let copies = cascade_copies(&[4, 2, 2, 1, 0, 0]).unwrap();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(copies.iter().sum::<u64>(), 30);
+    }
+
+    #[test]
+    fn reports_overflowing_copies() {
+        let error = cascade_copies(&[10; 100]).unwrap_err();
+        assert!(error.to_string().starts_with("Copies of card"));
+    }
+}
@@ -13,7 +13,6 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 6", "Solving...");
     let mut total_part_1 = 1;
-    let mut total_part_2 = 0;
 
     let mut line = reader.lines();
     let mut values = line.next().unwrap().unwrap().trim().split(':').collect::<Vec<&str>>()[1].to_string();
@@ -25,25 +24,120 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     let distances: Vec<i64> = values.split_whitespace().map(|x| x.parse::<i64>().unwrap()).collect::<Vec<i64>>();
     let one_distance: String = values.split_whitespace().collect();
-    
-    for (i, time) in times.iter().enumerate() {
-        total_part_1 *= race(distances[i], *time);
-    }
 
-    total_part_2 += race(one_distance.parse::<i64>().unwrap(), one_time.parse::<i64>().unwrap());
+    let races: Vec<Race> = times.iter().zip(distances.iter()).map(|(t, d)| Race::new(*t, *d)).collect();
+    let one_race = Race::new(one_time.parse::<i64>().unwrap(), one_distance.parse::<i64>().unwrap());
 
-    fn race(d: i64, time: i64) -> i64 {
-        let mut winner = 0;
-        for t in 1..time {
-            if ((time - t) * t) > d {
-                winner += 1;
+    if args.table {
+        println!("race,hold,distance,beats_record");
+        for (i, race) in races.iter().enumerate() {
+            for (hold, distance) in race.curve() {
+                println!("{},{},{},{}", i + 1, hold, distance, distance > race.record);
             }
         }
-        winner
+        return Ok(());
+    }
+
+    for (i, race) in races.iter().enumerate() {
+        println!("Race {}: {}", i + 1, race.report());
+        total_part_1 *= race.ways_to_win();
     }
+    println!("Single race: {}", one_race.report());
 
     println!("Part 1: {}", total_part_1);
-    println!("Part 2: {}", total_part_2);
+    println!("Part 2: {}", one_race.ways_to_win());
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Race {
+    time: i64,
+    record: i64,
+}
+
+impl Race {
+    fn new(time: i64, record: i64) -> Race {
+        Race { time, record }
+    }
+
+    fn distance(&self, hold: i64) -> i64 {
+        (self.time - hold) * hold
+    }
+
+    /// The distance curve peaks at half the race time; for odd times both
+    /// neighbours of the midpoint tie, so the shorter hold is reported.
+    fn optimal_hold(&self) -> i64 {
+        self.time / 2
+    }
+
+    fn max_distance(&self) -> i64 {
+        self.distance(self.optimal_hold())
+    }
+
+    fn margin(&self) -> i64 {
+        self.max_distance() - self.record
+    }
+
+    /// Inclusive range of hold times that beat the record, or `None` if the
+    /// record can't be beaten. Solves `(time - h) * h > record` with the
+    /// quadratic formula and then nudges the float estimate onto exact integers.
+    fn winning_window(&self) -> Option<(i64, i64)> {
+        if self.margin() <= 0 {
+            return None;
+        }
+        let discriminant = ((self.time * self.time - 4 * self.record) as f64).sqrt();
+        let mut lo = ((self.time as f64 - discriminant) / 2.0).floor() as i64;
+        while self.distance(lo) <= self.record {
+            lo += 1;
+        }
+        while lo > 0 && self.distance(lo - 1) > self.record {
+            lo -= 1;
+        }
+        Some((lo, self.time - lo))
+    }
+
+    fn ways_to_win(&self) -> i64 {
+        match self.winning_window() {
+            Some((lo, hi)) => hi - lo + 1,
+            None => 0,
+        }
+    }
+
+    fn curve(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (0..=self.time).map(|hold| (hold, self.distance(hold)))
+    }
+
+    fn report(&self) -> String {
+        let window = match self.winning_window() {
+            Some((lo, hi)) => format!("[{}, {}]", lo, hi),
+            None => String::from("none"),
+        };
+        format!(
+            "time {}, record {}, optimal hold {}, max distance {}, margin {}, winning window {}",
+            self.time, self.record, self.optimal_hold(), self.max_distance(), self.margin(), window
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winning_window_excludes_holds_that_only_tie_the_record() {
+        let race = Race::new(30, 200);
+        assert_eq!(race.distance(10), 200);
+        assert_eq!(race.winning_window(), Some((11, 19)));
+        assert_eq!(race.ways_to_win(), 9);
+    }
+
+    #[test]
+    fn unbeatable_record_has_no_window() {
+        let race = Race::new(7, 12);
+        assert_eq!(race.margin(), 0);
+        assert_eq!(race.winning_window(), None);
+        assert_eq!(race.ways_to_win(), 0);
+        assert_eq!(Race::new(7, 100).winning_window(), None);
+    }
+}
@@ -1,10 +1,15 @@
-use std::{io::BufRead, collections::{HashMap, BinaryHeap, VecDeque}, cmp::Ordering};
+use std::{io::BufRead, collections::BinaryHeap, cmp::Ordering};
 
 use crate::helpers::{self, Args};
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use itertools::Itertools;
 use log::info;
 
+const DEFAULT_CARDS: &str = "23456789TJQKA";
+const DEFAULT_WILDCARD: char = 'J';
+const DEFAULT_HAND_SIZE: usize = 5;
+const DEFAULT_LADDER: &str = "HighCard=1,OnePair=2,TwoPair=2+2,ThreeOfAKind=3,FullHouse=3+2,FourOfAKind=4,FiveOfAKind=5";
+
 pub fn run(args: &mut Args) -> Result<()> {
     info!(target: "Day 7", "Running...");
     info!(target: "Day 7", "Parsing input from file");
@@ -14,34 +19,41 @@ pub fn run(args: &mut Args) -> Result<()> {
 
     info!(target: "Day 7", "Solving...");
 
+    let (standard, jokers) = Ruleset::from_args(args).with_context(|| "Invalid Camel Cards configuration")?;
+
     let mut heap: BinaryHeap<Hand> = BinaryHeap::new();
-    let mut heap_with_joker: BinaryHeap<HandWithJoker> = BinaryHeap::new();
-    
-    for line in reader.lines() {
+    let mut heap_with_joker: BinaryHeap<Hand> = BinaryHeap::new();
+
+    for (idx, line) in reader.lines().enumerate() {
         let values = line.with_context(|| "Couldn't read line")?;
         let raw_hand = values.split_whitespace().collect::<Vec<&str>>()[0].to_string();
         let bid = values.split_whitespace().collect::<Vec<&str>>()[1].parse::<i32>().unwrap();
-        let hand_type = determine_type(&raw_hand.clone());
-        let hand_type_with_joker = determine_type_with_joker(&raw_hand.clone());
-        let value = raw_hand_to_values(&raw_hand.clone());
-        let value_with_joker = raw_hand_to_values_with_joker(&raw_hand.clone());
-        
-        let hand = Hand {
-            value,
-            hand_type,
-            bid,
-        };
 
-        let hand_with_joker = HandWithJoker {
-            value: value_with_joker,
-            hand_type: hand_type_with_joker,
-            bid,
-        };
+        heap.push(Hand::new(&raw_hand, bid, &standard).with_context(|| format!("Bad hand on line {}", idx + 1))?);
+        heap_with_joker.push(Hand::new(&raw_hand, bid, &jokers).with_context(|| format!("Bad hand on line {}", idx + 1))?);
+    }
 
-        heap.push(hand);
-        heap_with_joker.push(hand_with_joker);
+    if args.explain {
+        println!("rules,rank,hand,type,joker_type,best_substitution,bid,winnings");
+        for (rules, heap) in [("standard", heap), ("jokers", heap_with_joker)] {
+            for (i, hand) in heap.into_sorted_vec().iter().enumerate() {
+                let rank = i as i32 + 1;
+                println!(
+                    "{},{},{},{},{},{},{},{}",
+                    rules,
+                    rank,
+                    hand.cards,
+                    standard.type_name(standard.hand_type(&hand.cards)),
+                    jokers.type_name(jokers.hand_type(&hand.cards)),
+                    jokers.best_substitution(&hand.cards),
+                    hand.bid,
+                    rank * hand.bid
+                );
+            }
+        }
+        return Ok(());
     }
-    
+
     let mut total_part_1 = 0;
     let mut total_part_2 = 0;
     let mut number_of_hands = heap.len();
@@ -59,73 +71,200 @@ pub fn run(args: &mut Args) -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
+/// Position of a hand on the ruleset's ladder: 0 when no rung is reached,
+/// otherwise one more than the index of the strongest rung reached.
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
+struct HandType(usize);
+
+/// One step of the hand-type ladder, e.g. `FullHouse=3+2`: a hand reaches it
+/// when it holds groups of at least these sizes, each of a different card.
+#[derive(Debug, Clone)]
+struct Rung {
+    name: String,
+    groups: Vec<usize>,
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
-enum CardValue {
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
+impl Rung {
+    fn parse(text: &str) -> Result<Rung> {
+        let (name, groups) = match text.split_once('=') {
+            Some((name, groups)) => (name.trim(), groups.trim()),
+            None => (text.trim(), text.trim()),
+        };
+        let mut groups = groups
+            .split('+')
+            .map(|g| g.trim().parse::<usize>().with_context(|| format!("Bad group size '{}' in rung '{}'", g, text)))
+            .collect::<Result<Vec<usize>>>()?;
+        ensure!(groups.iter().all(|&g| g > 0), "Rung '{}' has an empty group", text);
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(Rung { name: name.to_string(), groups })
+    }
+
+    /// Cards that wildcards must supply to reach this rung from a hand whose
+    /// group sizes are given largest first, with `fresh_cards` other cards
+    /// available to start new groups. Pairing the biggest required group
+    /// with the biggest held group is always the cheapest assignment. `None`
+    /// if the rung needs more groups than there are cards to make them from.
+    fn shortfall(&self, signature: &[usize], fresh_cards: usize) -> Option<usize> {
+        if self.groups.len() > signature.len() + fresh_cards {
+            return None;
+        }
+        Some(
+            self.groups
+                .iter()
+                .enumerate()
+                .map(|(i, &g)| g.saturating_sub(signature.get(i).copied().unwrap_or(0)))
+                .sum(),
+        )
+    }
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
-enum CardValueWithJoker {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Queen,
-    King,
-    Ace,
+/// Card order (weakest first) plus an optional wildcard that counts as
+/// whatever card makes the strongest hand type, while ranking by its own
+/// position in the order for tie-breaks. Also carries the hand size and the
+/// hand-type ladder (weakest rung first).
+#[derive(Debug, Clone)]
+struct Ruleset {
+    order: Vec<char>,
+    wildcard: Option<char>,
+    hand_size: usize,
+    ladder: Vec<Rung>,
 }
 
+impl Ruleset {
+    fn new(order: &str, wildcard: Option<char>, hand_size: usize, ladder: &str) -> Result<Ruleset> {
+        let order: Vec<char> = order.chars().collect();
+        ensure!(!order.is_empty(), "Card order is empty");
+        ensure!(order.iter().all_unique(), "Card order '{}' repeats a card", order.iter().collect::<String>());
+        if let Some(wildcard) = wildcard {
+            ensure!(order.contains(&wildcard), "Wildcard '{}' is not in the card order", wildcard);
+        }
+        ensure!(hand_size > 0, "Hand size must be at least 1");
+
+        let ladder = ladder.split(',').map(Rung::parse).collect::<Result<Vec<Rung>>>()?;
+        for rung in &ladder {
+            let cards: usize = rung.groups.iter().sum();
+            ensure!(cards <= hand_size, "Rung '{}' needs {} cards but hands only have {}", rung.name, cards, hand_size);
+        }
 
-#[derive(Debug, Eq)]
-struct Hand {
-    value: VecDeque<CardValue>,
-    hand_type: HandType,
-    bid: i32,
+        Ok(Ruleset { order, wildcard, hand_size, ladder })
+    }
+
+    /// Builds the part 1 and part 2 rulesets. The joker variant uses the
+    /// same cards with the wildcard moved to the bottom of the order.
+    fn from_args(args: &Args) -> Result<(Ruleset, Ruleset)> {
+        let cards = args.cards.as_deref().unwrap_or(DEFAULT_CARDS);
+        let wildcard = args.wildcard.unwrap_or(DEFAULT_WILDCARD);
+        let hand_size = args.hand_size.unwrap_or(DEFAULT_HAND_SIZE);
+        let ladder = args.ladder.as_deref().unwrap_or(DEFAULT_LADDER);
+
+        ensure!(cards.contains(wildcard), "Wildcard '{}' is not in the card order '{}'", wildcard, cards);
+        let joker_cards: String = [wildcard].into_iter().chain(cards.chars().filter(|&c| c != wildcard)).collect();
+
+        Ok((
+            Ruleset::new(cards, None, hand_size, ladder)?,
+            Ruleset::new(&joker_cards, Some(wildcard), hand_size, ladder)?,
+        ))
+    }
+
+    fn card_value(&self, card: char) -> Option<usize> {
+        self.order.iter().position(|&c| c == card)
+    }
+
+    /// Non-wildcard cards with how many of each the hand holds, most common
+    /// first and strongest first on ties, along with the wildcard count.
+    fn groups(&self, hand: &str) -> (Vec<(char, usize)>, usize) {
+        let wildcards = hand.chars().filter(|&c| Some(c) == self.wildcard).count();
+        let groups = hand
+            .chars()
+            .filter(|&c| Some(c) != self.wildcard)
+            .counts()
+            .into_iter()
+            .sorted_unstable_by_key(|&(c, n)| std::cmp::Reverse((n, self.card_value(c))))
+            .collect();
+        (groups, wildcards)
+    }
+
+    /// Classifies a hand from its count signature: how many of each distinct
+    /// card it holds, largest first. The strongest rung whose shortfall the
+    /// wildcards can cover wins.
+    fn hand_type(&self, hand: &str) -> HandType {
+        let (groups, wildcards) = self.groups(hand);
+        let signature: Vec<usize> = groups.iter().map(|&(_, n)| n).collect();
+        let fresh_cards = self.order.len() - usize::from(self.wildcard.is_some()) - groups.len();
+        HandType(
+            self.ladder
+                .iter()
+                .rposition(|rung| rung.shortfall(&signature, fresh_cards).is_some_and(|n| n <= wildcards))
+                .map_or(0, |i| i + 1),
+        )
+    }
+
+    fn type_name(&self, hand_type: HandType) -> &str {
+        match hand_type.0 {
+            0 => "None",
+            n => &self.ladder[n - 1].name,
+        }
+    }
+
+    /// The hand with every wildcard swapped for cards that reach its hand
+    /// type: first topping up the groups the rung needs, then joining the
+    /// biggest group. Missing groups use the strongest cards not in the hand.
+    fn best_substitution(&self, hand: &str) -> String {
+        let Some(wildcard) = self.wildcard else {
+            return hand.to_string();
+        };
+        let (mut groups, _) = self.groups(hand);
+        let unused = self
+            .order
+            .iter()
+            .rev()
+            .copied()
+            .filter(|&c| c != wildcard && !groups.iter().any(|&(g, _)| g == c))
+            .collect::<Vec<char>>();
+        groups.extend(unused.into_iter().map(|c| (c, 0)));
+
+        let mut fills: Vec<char> = Vec::new();
+        if let HandType(n @ 1..) = self.hand_type(hand) {
+            for (i, &needed) in self.ladder[n - 1].groups.iter().enumerate() {
+                let (card, held) = groups.get(i).copied().unwrap_or((wildcard, needed));
+                fills.extend(std::iter::repeat_n(card, needed.saturating_sub(held)));
+            }
+        }
+        let mut fills = fills.into_iter();
+        let top = groups.first().map_or(wildcard, |&(c, _)| c);
+        hand.chars()
+            .map(|c| if c == wildcard { fills.next().unwrap_or(top) } else { c })
+            .collect()
+    }
 }
 
 #[derive(Debug, Eq)]
-struct HandWithJoker {
-    value: VecDeque<CardValueWithJoker>,
+struct Hand {
+    cards: String,
+    value: Vec<usize>,
     hand_type: HandType,
     bid: i32,
 }
 
-impl PartialEq for Hand {
-    fn eq(&self, other: &Self) -> bool {
-        self.hand_type == other.hand_type && self.value == other.value
+impl Hand {
+    fn new(raw_hand: &str, bid: i32, ruleset: &Ruleset) -> Result<Hand> {
+        let count = raw_hand.chars().count();
+        ensure!(count == ruleset.hand_size, "Hand '{}' has {} cards, expected {}", raw_hand, count, ruleset.hand_size);
+        let value = raw_hand
+            .chars()
+            .map(|c| ruleset.card_value(c).with_context(|| format!("Can't find a matching card value for '{}'", c)))
+            .collect::<Result<Vec<usize>>>()?;
+
+        Ok(Hand {
+            cards: raw_hand.to_string(),
+            value,
+            hand_type: ruleset.hand_type(raw_hand),
+            bid,
+        })
     }
 }
 
-impl PartialEq for HandWithJoker {
+impl PartialEq for Hand {
     fn eq(&self, other: &Self) -> bool {
         self.hand_type == other.hand_type && self.value == other.value
     }
@@ -142,217 +281,109 @@ impl Ord for Hand {
     }
 }
 
-impl Ord for HandWithJoker {
-    fn cmp(&self, other: &Self) -> Ordering {
-       let compare_by_hand_type = self.hand_type.cmp(&other.hand_type);
-       match compare_by_hand_type {
-        Ordering::Greater => {Ordering::Greater},
-        Ordering::Less => {Ordering::Less},
-        Ordering::Equal => {self.value.cmp(&other.value)}
-       }
-    }
-}
-
 impl PartialOrd for Hand {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl PartialOrd for HandWithJoker {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-fn raw_hand_to_values(hand: &str) -> VecDeque<CardValue> {
-    let mut values: VecDeque<CardValue> = VecDeque::new();
-    for c in hand.chars() {
-        match c {
-            '2' => values.push_back(CardValue::Two),
-            '3' => values.push_back(CardValue::Three),
-            '4' => values.push_back(CardValue::Four),
-            '5' => values.push_back(CardValue::Five),
-            '6' => values.push_back(CardValue::Six),
-            '7' => values.push_back(CardValue::Seven),
-            '8' => values.push_back(CardValue::Eight),
-            '9' => values.push_back(CardValue::Nine),
-            'T' => values.push_back(CardValue::Ten),
-            'J' => values.push_back(CardValue::Jack),
-            'Q' => values.push_back(CardValue::Queen),
-            'K' => values.push_back(CardValue::King),
-            'A' => values.push_back(CardValue::Ace),
-            _ => panic!("Can't find a matching card value"),
-        }
-    }
-    values
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn raw_hand_to_values_with_joker(hand: &str) -> VecDeque<CardValueWithJoker> {
-    let mut values: VecDeque<CardValueWithJoker> = VecDeque::new();
-    for c in hand.chars() {
-        match c {
-            '2' => values.push_back(CardValueWithJoker::Two),
-            '3' => values.push_back(CardValueWithJoker::Three),
-            '4' => values.push_back(CardValueWithJoker::Four),
-            '5' => values.push_back(CardValueWithJoker::Five),
-            '6' => values.push_back(CardValueWithJoker::Six),
-            '7' => values.push_back(CardValueWithJoker::Seven),
-            '8' => values.push_back(CardValueWithJoker::Eight),
-            '9' => values.push_back(CardValueWithJoker::Nine),
-            'T' => values.push_back(CardValueWithJoker::Ten),
-            'J' => values.push_back(CardValueWithJoker::Joker),
-            'Q' => values.push_back(CardValueWithJoker::Queen),
-            'K' => values.push_back(CardValueWithJoker::King),
-            'A' => values.push_back(CardValueWithJoker::Ace),
-            _ => panic!("Can't find a matching card value"),
-        }
+    fn standard() -> Ruleset {
+        Ruleset::new(DEFAULT_CARDS, None, DEFAULT_HAND_SIZE, DEFAULT_LADDER).unwrap()
     }
-    values
-}
-
-fn determine_type(hand: &str) -> HandType {
-    is_five_of_a_kind(hand)
-}
 
-fn determine_type_with_joker(hand: &str) -> HandType {
-    let starting_type = is_five_of_a_kind(hand);
-    if starting_type != HandType::FiveOfAKind {
-        for c in hand.chars() {
-            if c == 'J' {
-                return get_hand_type_optimized(hand);
-            }
-        }
+    fn jokers() -> Ruleset {
+        Ruleset::new("J23456789TQKA", Some('J'), DEFAULT_HAND_SIZE, DEFAULT_LADDER).unwrap()
     }
-    starting_type
-}
 
-fn get_hand_type_optimized(hand: &str) -> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    if map[&'J'] == 4 {
-        return HandType::FiveOfAKind;
-    }
-    if map[&'J'] == 3 {
-        if map.values().contains(&2) {
-            return HandType::FiveOfAKind;
-        }
-        return HandType::FourOfAKind;
+    fn type_of(ruleset: &Ruleset, hand: &str) -> String {
+        ruleset.type_name(ruleset.hand_type(hand)).to_string()
     }
 
-    if map[&'J'] == 2 {
-        if map.values().contains(&3) {
-            return HandType::FiveOfAKind;
+    /// Best type reachable by trying every non-wildcard card in every
+    /// wildcard slot independently.
+    fn brute_force_type(hand: &str, ruleset: &Ruleset, plain: &Ruleset) -> HandType {
+        let wildcard = ruleset.wildcard.unwrap();
+        let substitutes: Vec<char> = ruleset.order.iter().copied().filter(|&c| c != wildcard).collect();
+        let slots: Vec<usize> = hand.chars().positions(|c| c == wildcard).collect();
+        if slots.is_empty() {
+            return plain.hand_type(hand);
         }
-        if map.values().contains(&2) {
-            let mut count = 0;
-            for (_, v) in map {
-                if v == 2 {
-                    count += 1;
+        slots
+            .iter()
+            .map(|_| substitutes.iter())
+            .multi_cartesian_product()
+            .map(|choice| {
+                let mut cards: Vec<char> = hand.chars().collect();
+                for (slot, card) in slots.iter().zip(choice) {
+                    cards[*slot] = *card;
                 }
-            }
-            if count == 2 {
-                return HandType::FourOfAKind;
-            }
-            return HandType::ThreeOfAKind;
-        }
-        return HandType::ThreeOfAKind;
+                plain.hand_type(&cards.iter().collect::<String>())
+            })
+            .max()
+            .unwrap()
     }
 
-    if map[&'J'] == 1 {
-        if map.values().contains(&4) {
-            return HandType::FiveOfAKind;
+    fn check_against_brute_force(ruleset: &Ruleset, plain: &Ruleset) {
+        for cards in ruleset.order.iter().combinations_with_replacement(ruleset.hand_size) {
+            let hand: String = cards.into_iter().collect();
+            let expected = brute_force_type(&hand, ruleset, plain);
+            assert_eq!(ruleset.hand_type(&hand), expected, "hand {}", hand);
+            assert_eq!(plain.hand_type(&ruleset.best_substitution(&hand)), expected, "hand {}", hand);
         }
-        if map.values().contains(&3) {
-            return HandType::FourOfAKind;
-        }
-        if map.values().contains(&2) {
-            let mut count = 0;
-            for (_, v) in map {
-                if v == 2 {
-                    count += 1;
-                }
-            }
-            if count == 2 {
-                return HandType::FullHouse;
-            }
-            return HandType::ThreeOfAKind;
-        }
-    }
-    HandType::OnePair
-}
-
-fn is_five_of_a_kind(hand: &str)-> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    if map.len() == 1 {
-        return HandType::FiveOfAKind;
     }
-    is_four_of_a_kind(hand)
-}
 
-fn is_four_of_a_kind(hand: &str) -> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    if map.values().contains(&4) {
-        return HandType::FourOfAKind
+    #[test]
+    fn can_classify_hands_by_signature() {
+        let standard = standard();
+        let jokers = jokers();
+        assert_eq!(type_of(&standard, "AAAAA"), "FiveOfAKind");
+        assert_eq!(type_of(&standard, "AA8AA"), "FourOfAKind");
+        assert_eq!(type_of(&standard, "23332"), "FullHouse");
+        assert_eq!(type_of(&standard, "TTT98"), "ThreeOfAKind");
+        assert_eq!(type_of(&standard, "23432"), "TwoPair");
+        assert_eq!(type_of(&standard, "A23A4"), "OnePair");
+        assert_eq!(type_of(&standard, "23456"), "HighCard");
+        assert_eq!(type_of(&jokers, "KTJJT"), "FourOfAKind");
+        assert_eq!(type_of(&jokers, "JJJJJ"), "FiveOfAKind");
     }
-    is_full_house(hand)
-}
 
-fn is_full_house(hand: &str) -> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    if map.values().contains(&3) && map.values().contains(&2) {
-        return HandType::FullHouse
+    // Hand type only depends on which cards are held, not their order, so
+    // every multiset of cards stands in for all of its permutations.
+    #[test]
+    fn joker_classifier_matches_brute_force_for_every_hand() {
+        check_against_brute_force(&jokers(), &standard());
     }
-    is_three_of_a_kind(hand)
-}
 
-fn is_three_of_a_kind(hand: &str) -> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    if map.values().contains(&3) && map.values().contains(&1) {
-        return HandType::ThreeOfAKind
+    #[test]
+    fn joker_classifier_matches_brute_force_for_custom_rules() {
+        let ladder = "1,3,2+2,2+2+2,4+2,6";
+        let ruleset = Ruleset::new("W1234", Some('W'), 6, ladder).unwrap();
+        let plain = Ruleset::new("W1234", None, 6, ladder).unwrap();
+        check_against_brute_force(&ruleset, &plain);
     }
-    is_two_pair(hand)
-}
 
-fn is_two_pair(hand: &str) -> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    let mut twos: i32 = 0;
-    for value in map.values() {
-        if value == &2 {
-            twos += 1;
+    #[test]
+    fn joker_classifier_matches_brute_force_for_tiny_alphabets() {
+        for (cards, ladder) in [("J2", "1,2,2+2"), ("J23", "1,2,1+1+1,2+2,4")] {
+            let ruleset = Ruleset::new(cards, Some('J'), 4, ladder).unwrap();
+            let plain = Ruleset::new(cards, None, 4, ladder).unwrap();
+            check_against_brute_force(&ruleset, &plain);
         }
+        let ruleset = Ruleset::new("J2", Some('J'), 4, "1,2,2+2").unwrap();
+        assert_eq!(type_of(&ruleset, "JJJJ"), "2");
     }
-    if twos == 2 {
-        return HandType::TwoPair;
-    }
-    is_one_pair(hand)
-}
 
-fn is_one_pair(hand: &str) -> HandType {
-    let map: HashMap<char, u32> = count_cards(hand);
-    let mut twos: i32 = 0;
-    let mut threes: i32 = 0;
-    for value in map.values() {
-        if value == &2 {
-            twos += 1;
-        }
-        if value == &3 {
-            threes += 1;
-        }
-    }
-    if twos == 1 && threes == 0 {
-        return HandType::OnePair;
+    #[test]
+    fn rejects_bad_configuration() {
+        assert!(Ruleset::new("AAK", None, 5, DEFAULT_LADDER).is_err());
+        assert!(Ruleset::new("AKQ", Some('J'), 5, DEFAULT_LADDER).is_err());
+        assert!(Ruleset::new(DEFAULT_CARDS, None, 4, DEFAULT_LADDER).is_err());
+        assert!(Ruleset::new(DEFAULT_CARDS, None, 5, "1,2+x").is_err());
+        assert!(Hand::new("AAAA", 1, &standard()).is_err());
+        assert!(Hand::new("AAAAX", 1, &standard()).is_err());
     }
-    HandType::HighCard
 }
-
-fn count_cards(hand: &str) -> HashMap<char, u32> {
-    let map: HashMap<char, u32> = hand
-        .chars()
-        .sorted()
-        .into_group_map_by(|&x| x)
-        .into_iter()
-        .map(|(k, v)| (k, v.len() as u32))
-        .collect();
-
-    map
-}
\ No newline at end of file
@@ -1,8 +1,8 @@
-use std::io::BufRead;
+use std::{io::BufRead, fmt};
 
-use crate::helpers::{self, Args};
-use anyhow::{Context, Result};
-use log::info;
+use crate::helpers::{self, Args, Unexplained};
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
 
 pub fn run(args: &mut Args) -> Result<()> {
     info!(target: "Day 9", "Running...");
@@ -12,52 +12,350 @@ pub fn run(args: &mut Args) -> Result<()> {
         .with_context(|| "Problem with buffered file read")?;
 
     info!(target: "Day 9", "Solving...");
-    let mut total_part_1 = 0;
-    let mut total_part_2 = 0;
-    let lines = reader.lines();
-    let mut intermediate_vectors_part1: Vec<Vec<i32>> = Vec::new();
-    let mut intermediate_vectors_part2: Vec<Vec<i32>> = Vec::new();
+    let steps = args.steps.unwrap_or(1);
+    let mut total_part_1: i128 = 0;
+    let mut total_part_2: i128 = 0;
 
-    for line in lines {
-        let mut vec: Vec<i32> = line.unwrap().split_whitespace().map(|x| x.parse::<i32>().unwrap()).collect::<Vec<i32>>();
-        while !is_all_zeros(&vec) {
-            intermediate_vectors_part1.push(vec.clone());
-            intermediate_vectors_part2.push(vec.clone());
-            vec = vec_diff(vec);
-        }
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| "Couldn't read line")?;
+        let Some(values) = parse_sequence(&line).with_context(|| format!("Bad number on line {}", idx + 1))? else {
+            continue;
+        };
+        let history = History::new(values.clone()).with_context(|| format!("Line {}", idx + 1))?;
 
-        let mut previous_element = 0;
-        while let Some(last_element) = intermediate_vectors_part1.pop() {
-            previous_element += last_element.iter().last().unwrap();
+        if !history.explained {
+            let message = unexplained_message(idx + 1, &values, &history);
+            match args.on_unexplained {
+                Unexplained::Error => bail!(message),
+                Unexplained::Skip => {
+                    warn!(target: "Day 9", "{}, skipping", message);
+                    continue;
+                }
+            }
         }
-        total_part_1 += previous_element;
 
-        previous_element = 0;
-        while let Some(first_element) = intermediate_vectors_part2.pop() {
-            previous_element = first_element.first().unwrap() - previous_element;
+        if args.polynomial {
+            let coefficients = history.polynomial().with_context(|| format!("Line {}", idx + 1))?;
+            let reproduces = values
+                .iter()
+                .enumerate()
+                .all(|(n, &v)| evaluate(&coefficients, n as i128) == Some(Fraction::from(v)));
+            println!(
+                "Line {}: degree {}, f(n) = {}, {}",
+                idx + 1,
+                history.degree(),
+                format_polynomial(&coefficients),
+                if reproduces { "reproduces input" } else { "DOES NOT reproduce input" }
+            );
+            continue;
         }
 
-        total_part_2 += previous_element;
+        let ahead = history.extrapolate_forward(steps).with_context(|| format!("Line {}", idx + 1))?;
+        let behind = history.extrapolate_backward(steps).with_context(|| format!("Line {}", idx + 1))?;
+        total_part_1 = total_part_1.checked_add(ahead).with_context(|| "Overflow summing forward predictions")?;
+        total_part_2 = total_part_2.checked_add(behind).with_context(|| "Overflow summing backward predictions")?;
+    }
 
+    if args.polynomial {
+        return Ok(());
     }
 
     println!("{}", total_part_1);
     println!("{}", total_part_2);
-    
+
     Ok(())
 }
 
-fn vec_diff(input: Vec<i32>) -> Vec<i32> {
+/// A sequence together with its difference table, one row per level,
+/// stopping at the first all-zero row. If the rows run out before any of
+/// them is all zeros, only a polynomial of degree `len - 1` fits, which
+/// any sequence has, so the history is marked as not explained.
+#[derive(Debug, Clone, PartialEq)]
+struct History {
+    differences: Vec<Vec<i128>>,
+    explained: bool,
+}
+
+impl History {
+    fn new(values: Vec<i128>) -> Result<History> {
+        let mut differences: Vec<Vec<i128>> = Vec::new();
+        let mut vec = values;
+        let explained = loop {
+            if vec.is_empty() {
+                break false;
+            }
+            if is_all_zeros(&vec) {
+                break true;
+            }
+            let next = vec_diff(&vec).with_context(|| format!("Overflow in difference row {}", differences.len() + 1))?;
+            differences.push(vec);
+            vec = next;
+        };
+        Ok(History { differences, explained })
+    }
+
+    /// Degree of the last row that isn't all zeros.
+    fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// Value `steps` places after the last one, by Newton's backward
+    /// difference formula: the sum of `C(steps + j - 1, j)` times the last
+    /// entry of row `j`. One step is just the sum of the last entries.
+    fn extrapolate_forward(&self, steps: u64) -> Result<i128> {
+        let lasts = self.differences.iter().map(|row| *row.last().unwrap());
+        weighted_sum(lasts, steps, false)
+    }
+
+    /// Value `steps` places before the first one. Same as going forward but
+    /// from the first entries, with alternating signs.
+    fn extrapolate_backward(&self, steps: u64) -> Result<i128> {
+        let firsts = self.differences.iter().map(|row| *row.first().unwrap());
+        weighted_sum(firsts, steps, true)
+    }
+}
+
+impl History {
+    /// Exact coefficients of the polynomial through the history, lowest
+    /// power first, with `n = 0` at the first value. Built from Newton's
+    /// forward form: the first entry of row `j` times `C(n, j)`.
+    fn polynomial(&self) -> Result<Vec<Fraction>> {
+        let overflow = || "Overflow fitting polynomial";
+        let mut coefficients = vec![Fraction::from(0); self.differences.len()];
+        // Integer coefficients of n(n - 1)...(n - j + 1), lowest power first
+        let mut falling: Vec<i128> = vec![1];
+        let mut factorial: i128 = 1;
+
+        for (j, row) in self.differences.iter().enumerate() {
+            if j > 0 {
+                let shift = j as i128 - 1;
+                let mut next = vec![0; falling.len() + 1];
+                for (p, &c) in falling.iter().enumerate() {
+                    next[p + 1] += c;
+                    next[p] = shift.checked_mul(c).and_then(|sc| next[p].checked_sub(sc)).with_context(overflow)?;
+                }
+                falling = next;
+                factorial = factorial.checked_mul(j as i128).with_context(overflow)?;
+            }
+            for (p, &c) in falling.iter().enumerate() {
+                let term = row[0].checked_mul(c).and_then(|n| Fraction::new(n, factorial)).with_context(overflow)?;
+                coefficients[p] = coefficients[p].checked_add(term).with_context(overflow)?;
+            }
+        }
+        Ok(coefficients)
+    }
+}
+
+/// Values on one line of input, or `None` for a blank line.
+fn parse_sequence(line: &str) -> Result<Option<Vec<i128>>> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    let values = line.split_whitespace().map(|x| x.parse::<i128>()).collect::<Result<Vec<i128>, _>>()?;
+    Ok(Some(values))
+}
+
+fn unexplained_message(line: usize, values: &[i128], history: &History) -> String {
+    if values.len() < 2 {
+        return format!("Line {}: a single value can't show a pattern, need at least 2", line);
+    }
+    format!(
+        "Line {}: no polynomial of degree < {} explains the sequence, differences reached degree {} without an all-zero row",
+        line,
+        values.len() - 1,
+        history.degree()
+    )
+}
+
+fn evaluate(coefficients: &[Fraction], n: i128) -> Option<Fraction> {
+    coefficients
+        .iter()
+        .rev()
+        .try_fold(Fraction::from(0), |acc, &c| acc.checked_mul(Fraction::from(n))?.checked_add(c))
+}
+
+fn format_polynomial(coefficients: &[Fraction]) -> String {
+    let mut formula = String::new();
+    for (power, c) in coefficients.iter().enumerate().rev() {
+        if c.numerator == 0 {
+            continue;
+        }
+        let magnitude = Fraction { numerator: c.numerator.abs(), denominator: c.denominator };
+        if formula.is_empty() {
+            if c.numerator < 0 {
+                formula.push('-');
+            }
+        } else {
+            formula.push_str(if c.numerator < 0 { " - " } else { " + " });
+        }
+        let variable = match power {
+            0 => String::new(),
+            1 => String::from("n"),
+            _ => format!("n^{}", power),
+        };
+        if power == 0 || magnitude.denominator == 1 && magnitude.numerator != 1 {
+            formula.push_str(&magnitude.to_string());
+        } else if magnitude.denominator != 1 {
+            formula.push_str(&format!("({})", magnitude));
+        }
+        formula.push_str(&variable);
+    }
+    if formula.is_empty() {
+        formula.push('0');
+    }
+    formula
+}
+
+/// Reduced fraction with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fraction {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Fraction {
+    fn new(numerator: i128, denominator: i128) -> Option<Fraction> {
+        if denominator == 0 {
+            return None;
+        }
+        let divisor = gcd(numerator, denominator);
+        let sign = if denominator < 0 { -1 } else { 1 };
+        Some(Fraction {
+            numerator: (numerator / divisor).checked_mul(sign)?,
+            denominator: (denominator / divisor).checked_mul(sign)?,
+        })
+    }
+
+    fn checked_add(self, other: Fraction) -> Option<Fraction> {
+        let numerator = self
+            .numerator
+            .checked_mul(other.denominator)?
+            .checked_add(other.numerator.checked_mul(self.denominator)?)?;
+        Fraction::new(numerator, self.denominator.checked_mul(other.denominator)?)
+    }
+
+    fn checked_mul(self, other: Fraction) -> Option<Fraction> {
+        Fraction::new(
+            self.numerator.checked_mul(other.numerator)?,
+            self.denominator.checked_mul(other.denominator)?,
+        )
+    }
+}
+
+impl From<i128> for Fraction {
+    fn from(value: i128) -> Fraction {
+        Fraction { numerator: value, denominator: 1 }
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1) as i128
+}
+
+fn weighted_sum(edges: impl Iterator<Item = i128>, steps: u64, alternate: bool) -> Result<i128> {
+    let overflow = || format!("Overflow extrapolating {} steps", steps);
+    let mut total: i128 = 0;
+    let mut binomial: i128 = 1;
+    for (j, edge) in edges.enumerate() {
+        if j > 0 {
+            // C(steps + j - 1, j) = C(steps + j - 2, j - 1) * (steps + j - 1) / j, always exact
+            binomial = binomial
+                .checked_mul(steps as i128 + j as i128 - 1)
+                .with_context(overflow)?
+                / j as i128;
+        }
+        let mut term = edge.checked_mul(binomial).with_context(overflow)?;
+        if alternate && j % 2 == 1 {
+            term = term.checked_neg().with_context(overflow)?;
+        }
+        total = total.checked_add(term).with_context(overflow)?;
+    }
+    Ok(total)
+}
+
+fn vec_diff(input: &[i128]) -> Option<Vec<i128>> {
     let vals = input.iter();
     let next_vals = input.iter().skip(1);
 
-    vals.zip(next_vals).map(|(cur, next)| next - cur).collect()
+    vals.zip(next_vals).map(|(cur, next)| next.checked_sub(*cur)).collect()
 }
 
-fn is_all_zeros(arr: &Vec<i32>) -> bool {
-    if arr.is_empty() {
-        return true;
+fn is_all_zeros(arr: &[i128]) -> bool {
+    !arr.is_empty() && arr.iter().all(|&item| item == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_extrapolate_several_steps_both_ways() {
+        let history = History::new(vec![10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(history.extrapolate_forward(1).unwrap(), 68);
+        assert_eq!(history.extrapolate_forward(2).unwrap(), 101);
+        assert_eq!(history.extrapolate_backward(1).unwrap(), 5);
+        assert_eq!(history.extrapolate_backward(2).unwrap(), -4);
+        assert_eq!(history.extrapolate_forward(0).unwrap(), 45);
+    }
+
+    #[test]
+    fn can_fit_exact_polynomial() {
+        let history = History::new(vec![1, 3, 6, 10, 15, 21]).unwrap();
+        let coefficients = history.polynomial().unwrap();
+        assert_eq!(format_polynomial(&coefficients), "(1/2)n^2 + (3/2)n + 1");
+        assert_eq!(evaluate(&coefficients, 6), Some(Fraction::from(28)));
+        assert_eq!(format_polynomial(&History::new(vec![5, 2, -1]).unwrap().polynomial().unwrap()), "-3n + 5");
+        assert_eq!(format_polynomial(&History::new(vec![0, 0]).unwrap().polynomial().unwrap()), "0");
     }
-    let first = 0;
-    arr.iter().all(|&item| item == first)
-}
\ No newline at end of file
+
+    #[test]
+    fn can_detect_sequences_without_zero_row() {
+        assert!(History::new(vec![0, 3, 6, 9]).unwrap().explained);
+        assert!(History::new(vec![0, 0]).unwrap().explained);
+        let unexplained = History::new(vec![1, 2, 4, 8, 16]).unwrap();
+        assert!(!unexplained.explained);
+        assert_eq!(unexplained.degree(), 4);
+        assert!(!History::new(vec![]).unwrap().explained);
+    }
+
+    #[test]
+    fn skips_blank_lines() {
+        assert_eq!(parse_sequence("0 3 6").unwrap(), Some(vec![0, 3, 6]));
+        assert_eq!(parse_sequence("").unwrap(), None);
+        assert_eq!(parse_sequence("   ").unwrap(), None);
+        assert!(parse_sequence("1 x 3").is_err());
+    }
+
+    #[test]
+    fn explains_why_short_sequences_are_unexplained() {
+        let single = History::new(vec![7]).unwrap();
+        assert!(!single.explained);
+        assert_eq!(
+            unexplained_message(3, &[7], &single),
+            "Line 3: a single value can't show a pattern, need at least 2"
+        );
+        let doubling = History::new(vec![1, 2, 4]).unwrap();
+        assert!(unexplained_message(1, &[1, 2, 4], &doubling).contains("degree < 2"));
+    }
+
+    #[test]
+    fn reports_overflow_instead_of_wrapping() {
+        assert!(History::new(vec![i128::MIN, i128::MAX]).is_err());
+        let history = History::new(vec![0, 1, 4, 9]).unwrap();
+        assert!(history.extrapolate_forward(u64::MAX).is_err());
+    }
+}
@@ -1,11 +1,11 @@
 use std::{
-    path::PathBuf, 
+    path::Path, 
     fs::File, 
     io::BufReader
 };
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 /// Program to select Advent of Code 2023 day to run
 #[derive(Debug, Parser)]
@@ -17,13 +17,93 @@ pub struct Args {
     /// Optional path to puzzle input file
     #[arg(short, long, value_name = "FILE", default_value = ".")]
     pub path: std::path::PathBuf,
+    /// Dump the full data table as CSV instead of the answers (days 3 and 6)
+    #[arg(long)]
+    pub table: bool,
+    /// Cubes in the bag as color=count pairs (day 2, default
+    /// red=12,green=13,blue=14)
+    #[arg(long)]
+    pub bag: Option<String>,
+    /// Only report symbols of this kind (day 3)
+    #[arg(long)]
+    pub symbol: Option<char>,
+    /// Only report symbols touching at least this many parts (day 3)
+    #[arg(long, value_name = "N")]
+    pub min_parts: Option<usize>,
+    /// Print a per-item breakdown of how the answers were reached (days 2,
+    /// 4 and 7)
+    #[arg(long)]
+    pub explain: bool,
+    /// Card order from weakest to strongest (day 7, default 23456789TJQKA)
+    #[arg(long)]
+    pub cards: Option<String>,
+    /// Card that acts as a joker in part 2 (day 7, default J)
+    #[arg(long)]
+    pub wildcard: Option<char>,
+    /// Number of cards in a hand (day 7, default 5)
+    #[arg(long)]
+    pub hand_size: Option<usize>,
+    /// Hand types from weakest to strongest as comma separated group sizes,
+    /// optionally named, e.g. "HighCard=1,OnePair=2,TwoPair=2+2,..." (day 7)
+    #[arg(long)]
+    pub ladder: Option<String>,
+    /// How many steps to extrapolate in each direction (day 9, default 1)
+    #[arg(long, value_name = "K")]
+    pub steps: Option<u64>,
+    /// Print the closed-form polynomial behind each sequence (day 9)
+    #[arg(long)]
+    pub polynomial: bool,
+    /// What to do with sequences no polynomial explains (day 9)
+    #[arg(long, value_enum, default_value_t = Unexplained::Error)]
+    pub on_unexplained: Unexplained,
+    /// Expansion factors to sum galaxy distances for, comma separated or
+    /// repeated (day 11, default 2 and 1000000)
+    #[arg(long, value_name = "N", value_delimiter = ',', value_parser = clap::value_parser!(i64).range(1..))]
+    pub expansion: Vec<i64>,
+    /// Galaxies to measure between, numbered in reading order; repeatable
+    /// (day 11)
+    #[arg(long, num_args = 2, value_names = ["I", "J"])]
+    pub pair: Vec<usize>,
+    /// Print nearest and farthest pairs and a distance histogram (day 11)
+    #[arg(long)]
+    pub stats: bool,
+    /// List each concrete arrangement, at most CAP per line (day 12,
+    /// default cap 100)
+    #[arg(long, value_name = "CAP", num_args = 0..=1, default_missing_value = "100")]
+    pub list: Option<usize>,
+    /// How many copies to unfold each row into for part 2 (day 12,
+    /// default 5, at most 1000)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..=1000))]
+    pub unfold: Option<u64>,
+    /// Cross-check the solver against a brute-force reference (day 12)
+    #[arg(long)]
+    pub verify: bool,
+    /// Read the input as nonogram row and column clues and solve the grid
+    /// (day 12)
+    #[arg(long)]
+    pub nonogram: bool,
+    /// Draw the puzzle map (day 3: the colored schematic, day 11: the
+    /// universe after expansion)
+    #[arg(long)]
+    pub render: bool,
+    /// Draw the map as an HTML page instead of terminal colors (day 3)
+    #[arg(long, requires = "render")]
+    pub html: bool,
     #[command(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Unexplained {
+    /// Stop with an error naming the line
+    Error,
+    /// Log a warning and leave the line out of the totals
+    Skip,
+}
+
 impl Args {
     pub fn resolve_path(&mut self) {
-        if self.path == PathBuf::from(".") {
+        if self.path == Path::new(".") {
             self.path.push("inputs");
             self.path.push(format!("day{}.txt", self.day))
         }